@@ -0,0 +1,134 @@
+// Adopting (`touch newcat.kitty`) and releasing (`rm`) kittens.
+
+use libc::{c_int, EACCES, EEXIST, EINVAL, EISDIR, ENOENT, ENOTDIR};
+use std::cell::RefCell;
+use std::cmp;
+use std::rc::Rc;
+
+use control::{is_control_dir, CONTROL};
+use dogs::DOG;
+use events::{Event, EVENTS_LOG, EVENT_STREAM};
+use {file, Either, Game, GameFile, Stat, JOURNAL, LEADERBOARD};
+
+impl Game {
+    // `touch`: adopts a kitten and says what it looks like
+    pub fn create(&mut self, parent: u64, name: &str) -> Result<Stat, c_int> {
+        let kitten = self.adopt(parent, name)?;
+        self.save();
+        Ok(self.stat(&Either::File { file: kitten }))
+    }
+
+    // `rm`: releases a kitten, which might be what a locked room (or the
+    // next chapter) was waiting for, or chases off the last dog, which might
+    // end the game
+    pub fn unlink(&mut self, parent: u64, name: &str) -> Result<(), c_int> {
+        self.release(parent, name)?;
+        self.catch_up_all();
+        self.unlock_rooms();
+        self.turn_pages();
+        if self.status().is_game_over() {
+            self.speedrun.finish(self.now_ms());
+        }
+        self.save();
+        Ok(())
+    }
+
+    // one past the biggest inode ever used, released kittens in the pet
+    // records included: the kernel and the leaderboard may still remember one
+    pub fn next_inode(&mut self) -> u64 {
+        let petted = self.pets.iter().map(|p| p.inode).max().unwrap_or(0);
+        self.last_inode = cmp::max(self.last_inode, petted) + 1;
+        self.last_inode
+    }
+
+    // adds a new kitten called `name` to the directory `parent`
+    fn adopt(&mut self, parent: u64, name: &str) -> Result<Rc<RefCell<GameFile>>, c_int> {
+        let dir = match self.inode_table.get(&parent) {
+            Some(Either::Directory { dir }) => Rc::clone(dir),
+            Some(Either::File { .. }) => return Err(ENOTDIR),
+            None => return Err(ENOENT),
+        };
+        if name == JOURNAL
+            || name == LEADERBOARD
+            || name == EVENTS_LOG
+            || name == EVENT_STREAM
+            || name == CONTROL
+            || name == DOG
+            || is_control_dir(&dir.borrow())
+        {
+            return Err(EACCES);
+        }
+        if name.is_empty() || name.contains('/') {
+            return Err(EINVAL);
+        }
+        let taken = self
+            .parent_table
+            .get(&parent)
+            .map(|children| children.iter().any(|c| c.name() == name));
+        if taken == Some(true) {
+            return Err(EEXIST);
+        }
+        let inode = self.next_inode();
+        let children = self.parent_table.entry(parent).or_default();
+
+        let kitten = Rc::new(RefCell::new(file(inode, name)));
+        dir.borrow_mut().files.push(Rc::clone(&kitten));
+        children.push(Either::File {
+            file: Rc::clone(&kitten),
+        });
+        self.inode_table.insert(
+            inode,
+            Either::File {
+                file: Rc::clone(&kitten),
+            },
+        );
+        self.log(Event::Adopted {
+            kitten: name.to_string(),
+        });
+        Ok(kitten)
+    }
+
+    // takes the kitten (or dog) called `name` out of the directory `parent`
+    fn release(&mut self, parent: u64, name: &str) -> Result<(), c_int> {
+        let dir = match self.inode_table.get(&parent) {
+            Some(Either::Directory { dir }) => Rc::clone(dir),
+            Some(Either::File { .. }) => return Err(ENOTDIR),
+            None => return Err(ENOENT),
+        };
+        let kitten = match self
+            .parent_table
+            .get(&parent)
+            .and_then(|children| children.iter().find(|c| c.name() == name))
+        {
+            Some(Either::File { file: f }) if f.borrow().is_kitten() => Rc::clone(f),
+            Some(Either::File { file: f }) if f.borrow().is_dog() => {
+                self.chase_off(&dir);
+                return Ok(());
+            }
+            Some(Either::File { .. }) => return Err(EACCES),
+            Some(Either::Directory { .. }) => return Err(EISDIR),
+            None => return Err(ENOENT),
+        };
+
+        kitten.borrow_mut().catch_up(self.now(), &self.needs);
+        let (inode, life) = {
+            let borrowed = kitten.borrow();
+            (borrowed.inode, borrowed.life)
+        };
+        if life != 0 {
+            self.abandoned += 1;
+        }
+        dir.borrow_mut().files.retain(|f| !Rc::ptr_eq(f, &kitten));
+        if let Some(children) = self.parent_table.get_mut(&parent) {
+            children.retain(|c| match c.file() {
+                Some(f) => !Rc::ptr_eq(f, &kitten),
+                None => true,
+            });
+        }
+        self.inode_table.remove(&inode);
+        self.log(Event::Released {
+            kitten: name.to_string(),
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+// Command line handling for the fuschia binary.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use fuse_game::dogs::Dogs;
+use fuse_game::generate::Settings;
+use fuse_game::needs::Needs;
+use fuse_game::trace::TraceArgs;
+use fuse_game::wander::Wandering;
+
+pub const USAGE: &str = "usage: fuschia <mountpoint> [level.json]
+       fuschia <mountpoint> --seed <n> [--depth <n>] [--fanout <n>] [--kittens <n>] [--pets <min>-<max>]
+                                       [--extension <ext>] [--hidden <n>]
+       fuschia <mountpoint> --resume <save.json>
+       fuschia --replay <trace> [level.json | --seed <n> ... | --resume <save.json>]
+
+       --save <save.json> keeps the game saved while it's played (--resume saves
+       back to the file it resumed from unless --save says otherwise)
+       --lonely-after <secs> makes kittens need another pet every <secs> seconds
+       --cooldown <secs> lets mad kittens calm down after <secs> seconds
+       --wander-every <secs> and --wander-after <ops> make needy kittens move to a
+       neighbouring directory every <secs> seconds or <ops> file-system operations
+       --dogs-every <secs> and --dogs-after <ops> let a dog into the game every <secs>
+       seconds or <ops> file-system operations, until it's chased off with rm
+       --record <trace> writes down every request and reply; --replay <trace> sends
+       them again without mounting and checks the replies match";
+
+pub struct Args {
+    // not needed for --replay
+    pub mountpoint: Option<OsString>,
+    pub level_file: Option<OsString>,
+    pub seed: Option<u64>,
+    pub generator: Settings,
+    pub resume: Option<PathBuf>,
+    pub save: Option<PathBuf>,
+    pub needs: Needs,
+    pub wandering: Wandering,
+    pub dogs: Dogs,
+    pub trace: TraceArgs,
+}
+
+fn value<T: FromStr>(flag: &str, value: Option<OsString>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .to_str()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| format!("{}: bad value {:?}", flag, value))
+}
+
+fn pets_range(value: &str) -> Option<(i32, i32)> {
+    let mut parts = value.splitn(2, '-');
+    let min = parts.next()?.parse().ok()?;
+    let max = match parts.next() {
+        Some(max) => max.parse().ok()?,
+        None => min,
+    };
+    Some((min, max))
+}
+
+pub fn parse<I: Iterator<Item = OsString>>(args: I) -> Result<Args, String> {
+    let (trace, rest) = TraceArgs::parse(args)?;
+    let mut args = rest.into_iter();
+    let mut positional = Vec::new();
+    let mut seed = None;
+    let mut generator = Settings::default();
+    let mut generator_flags = false;
+    let mut resume: Option<PathBuf> = None;
+    let mut save: Option<PathBuf> = None;
+    let mut needs = Needs::default();
+    let mut wandering = Wandering::default();
+    let mut dogs = Dogs::default();
+
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--seed") => seed = Some(value("--seed", args.next())?),
+            Some("--depth") => {
+                generator.depth = value("--depth", args.next())?;
+                generator_flags = true;
+            }
+            Some("--fanout") => {
+                generator.fanout = value("--fanout", args.next())?;
+                generator_flags = true;
+            }
+            Some("--kittens") => {
+                generator.kittens = value("--kittens", args.next())?;
+                generator_flags = true;
+            }
+            Some("--pets") => {
+                let range: String = value("--pets", args.next())?;
+                let (min, max) =
+                    pets_range(&range).ok_or_else(|| format!("--pets: bad range {:?}", range))?;
+                generator.min_pets = min;
+                generator.max_pets = max;
+                generator_flags = true;
+            }
+            Some("--extension") => {
+                generator.extension = value("--extension", args.next())?;
+                generator_flags = true;
+            }
+            Some("--hidden") => {
+                generator.hidden = value("--hidden", args.next())?;
+                generator_flags = true;
+            }
+            Some("--resume") => resume = Some(value("--resume", args.next())?),
+            Some("--save") => save = Some(value("--save", args.next())?),
+            Some("--lonely-after") => {
+                needs.lonely_after = Some(value("--lonely-after", args.next())?)
+            }
+            Some("--cooldown") => needs.cooldown = Some(value("--cooldown", args.next())?),
+            Some("--wander-every") => {
+                wandering.every_secs = Some(value("--wander-every", args.next())?)
+            }
+            Some("--wander-after") => {
+                wandering.every_ops = Some(value("--wander-after", args.next())?)
+            }
+            Some("--dogs-every") => dogs.every_secs = Some(value("--dogs-every", args.next())?),
+            Some("--dogs-after") => dogs.every_ops = Some(value("--dogs-after", args.next())?),
+            Some(flag) if flag.starts_with("--") => {
+                return Err(format!("unknown option {}", flag));
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    // a replay doesn't mount anything
+    let mountpoints = if trace.replay.is_some() { 0 } else { 1 };
+    if positional.len() < mountpoints || positional.len() > mountpoints + 1 {
+        return Err(if mountpoints == 0 {
+            "--replay takes, optionally, a level file (and no mountpoint)".to_string()
+        } else {
+            "expected a mountpoint and, optionally, a level file".to_string()
+        });
+    }
+    let has_level_file = positional.len() == mountpoints + 1;
+    if generator_flags && seed.is_none() {
+        return Err(
+            "--depth, --fanout, --kittens, --pets, --extension and --hidden need a --seed"
+                .to_string(),
+        );
+    }
+    if seed.is_some() && has_level_file {
+        return Err("use either a level file or --seed, not both".to_string());
+    }
+    if resume.is_some() && (seed.is_some() || has_level_file) {
+        return Err("--resume picks up a saved game, it can't take a level too".to_string());
+    }
+    if needs.lonely_after.is_some_and(|s| s < 1) || needs.cooldown.is_some_and(|s| s < 1) {
+        return Err("--lonely-after and --cooldown need at least 1 second".to_string());
+    }
+    if wandering.every_secs.is_some_and(|s| s < 1) || wandering.every_ops == Some(0) {
+        return Err("--wander-every and --wander-after need to be at least 1".to_string());
+    }
+    if dogs.every_secs.is_some_and(|s| s < 1) || dogs.every_ops == Some(0) {
+        return Err("--dogs-every and --dogs-after need to be at least 1".to_string());
+    }
+    if generator.fanout == 0 {
+        return Err("--fanout has to be at least 1".to_string());
+    }
+    if generator.hidden > generator.kittens {
+        return Err("--hidden can't hide more kittens than there are".to_string());
+    }
+    if generator.extension.is_empty() || generator.extension.contains('/') {
+        return Err("--extension needs to be a file extension like kitty".to_string());
+    }
+    if generator.min_pets < 1 || generator.min_pets > generator.max_pets {
+        return Err("--pets needs 1 <= min <= max".to_string());
+    }
+
+    let mut positional = positional.into_iter();
+    Ok(Args {
+        mountpoint: if mountpoints == 1 {
+            positional.next()
+        } else {
+            None
+        },
+        level_file: positional.next(),
+        seed: seed,
+        generator: generator,
+        save: save.or_else(|| resume.clone()),
+        resume: resume,
+        needs: needs,
+        wandering: wandering,
+        dogs: dogs,
+        trace: trace,
+    })
+}
@@ -0,0 +1,387 @@
+#![feature(const_str_len)]
+
+extern crate env_logger;
+extern crate fuse;
+extern crate fuse_game;
+extern crate libc;
+extern crate serde_json;
+extern crate time;
+
+mod args;
+mod spectate;
+#[cfg(test)]
+mod tests;
+mod xattrs;
+
+use fuse::consts::FOPEN_DIRECT_IO;
+use fuse::{
+    Filesystem, ReplyAttr, ReplyCreate, ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry,
+    ReplyOpen, ReplyWrite, ReplyXattr, Request,
+};
+use fuse_game::dogs::Pack;
+use fuse_game::ops;
+use fuse_game::trace::{self, Caller, Op, Ops, Recorder, Reply};
+use fuse_game::wander::Wander;
+use fuse_game::{generate, level, save, speedrun, Game, Opened};
+use libc::ENODATA;
+use std::env;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process;
+use time::Timespec;
+
+use spectate::Spectators;
+
+const TTL: Timespec = Timespec { sec: 1, nsec: 0 }; // 1 second
+
+// the game, mounted
+pub struct FuschiaFS {
+    game: Game,
+    // readers of `.events`, by file handle
+    spectators: Spectators,
+    // for --record
+    recorder: Option<Recorder>,
+}
+impl FuschiaFS {
+    pub fn new(game: Game) -> FuschiaFS {
+        FuschiaFS {
+            game: game,
+            spectators: Spectators::default(),
+            recorder: None,
+        }
+    }
+}
+
+impl Ops for FuschiaFS {
+    fn lookup(&mut self, caller: &Caller, parent: u64, name: &OsStr) -> Reply {
+        let reply = ops::lookup(&mut self.game, caller, parent, name);
+        self.wake_spectators();
+        reply
+    }
+
+    fn getattr(&mut self, _caller: &Caller, ino: u64) -> Reply {
+        let reply = ops::getattr(&mut self.game, ino);
+        self.wake_spectators();
+        reply
+    }
+
+    fn read(&mut self, _caller: &Caller, ino: u64, _fh: u64, offset: i64, size: u32) -> Reply {
+        let reply = ops::read(&mut self.game, ino, offset, size);
+        self.wake_spectators();
+        reply
+    }
+
+    fn readdir(&mut self, caller: &Caller, ino: u64, _fh: u64, offset: i64) -> Reply {
+        let reply = ops::readdir(&mut self.game, caller, ino, offset);
+        self.wake_spectators();
+        reply
+    }
+
+    fn write(
+        &mut self,
+        caller: &Caller,
+        ino: u64,
+        _fh: u64,
+        _offset: i64,
+        data: &[u8],
+        _flags: u32,
+    ) -> Reply {
+        let reply = ops::write(&mut self.game, caller, ino, data);
+        self.wake_spectators();
+        reply
+    }
+
+    fn open(&mut self, _caller: &Caller, ino: u64, flags: u32) -> Reply {
+        match self.game.open(ino, ops::writing(flags)) {
+            Ok(Opened::EventStream) => Reply::Opened {
+                fh: self.open_stream(),
+                flags: FOPEN_DIRECT_IO,
+            },
+            _ => ops::open(&mut self.game, ino, flags),
+        }
+    }
+
+    fn create(
+        &mut self,
+        _caller: &Caller,
+        parent: u64,
+        name: &OsStr,
+        _mode: u32,
+        _flags: u32,
+    ) -> Reply {
+        let reply = ops::create(&mut self.game, parent, name);
+        self.wake_spectators();
+        reply
+    }
+
+    fn mknod(
+        &mut self,
+        _caller: &Caller,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        _rdev: u32,
+    ) -> Reply {
+        let reply = ops::mknod(&mut self.game, parent, name, mode);
+        self.wake_spectators();
+        reply
+    }
+
+    fn unlink(&mut self, _caller: &Caller, parent: u64, name: &OsStr) -> Reply {
+        let reply = ops::unlink(&mut self.game, parent, name);
+        self.wake_spectators();
+        reply
+    }
+
+    fn setattr(&mut self, _caller: &Caller, ino: u64, size: Option<u64>) -> Reply {
+        ops::setattr(&mut self.game, ino, size)
+    }
+
+    fn recorder(&mut self) -> Option<&mut Recorder> {
+        self.recorder.as_mut()
+    }
+}
+
+impl Filesystem for FuschiaFS {
+    fn destroy(&mut self, _req: &Request) {
+        self.game.save();
+    }
+
+    fn lookup(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        trace::run(self, req, Op::lookup(parent, name)).send_entry(&TTL, reply)
+    }
+
+    fn getattr(&mut self, req: &Request, ino: u64, reply: ReplyAttr) {
+        trace::run(self, req, Op::Getattr { ino: ino }).send_attr(&TTL, reply)
+    }
+
+    // `.events` answers when it has something to say, so it isn't recorded;
+    // it doesn't tick either, or a replay would miss what the tick did
+    fn read(&mut self, req: &Request, ino: u64, fh: u64, offset: i64, size: u32, reply: ReplyData) {
+        if self.game.open(ino, false) == Ok(Opened::EventStream) {
+            return self.read_stream(fh, size, move |data| match data {
+                Ok(data) => reply.data(data),
+                Err(errno) => reply.error(errno),
+            });
+        }
+        let op = Op::Read {
+            ino: ino,
+            fh: fh,
+            offset: offset,
+            size: size,
+        };
+        trace::run(self, req, op).send_data(reply)
+    }
+
+    fn write(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        data: &[u8],
+        flags: u32,
+        reply: ReplyWrite,
+    ) {
+        let op = Op::Write {
+            ino: ino,
+            fh: fh,
+            offset: offset,
+            data: data.to_vec(),
+            flags: flags,
+        };
+        trace::run(self, req, op).send_written(reply)
+    }
+
+    fn readdir(&mut self, req: &Request, ino: u64, fh: u64, offset: i64, reply: ReplyDirectory) {
+        let op = Op::Readdir {
+            ino: ino,
+            fh: fh,
+            offset: offset,
+        };
+        trace::run(self, req, op).send_entries(reply)
+    }
+
+    fn open(&mut self, req: &Request, ino: u64, flags: u32, reply: ReplyOpen) {
+        let op = Op::Open {
+            ino: ino,
+            flags: flags,
+        };
+        trace::run(self, req, op).send_opened(reply)
+    }
+
+    fn mknod(
+        &mut self,
+        req: &Request,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        rdev: u32,
+        reply: ReplyEntry,
+    ) {
+        trace::run(self, req, Op::mknod(parent, name, mode, rdev)).send_entry(&TTL, reply)
+    }
+
+    fn create(
+        &mut self,
+        req: &Request,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        flags: u32,
+        reply: ReplyCreate,
+    ) {
+        trace::run(self, req, Op::create(parent, name, mode, flags)).send_created(&TTL, reply)
+    }
+
+    fn unlink(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        trace::run(self, req, Op::unlink(parent, name)).send_empty(reply)
+    }
+
+    fn setattr(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        _mode: Option<u32>,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+        size: Option<u64>,
+        _atime: Option<Timespec>,
+        _mtime: Option<Timespec>,
+        _fh: Option<u64>,
+        _crtime: Option<Timespec>,
+        _chgtime: Option<Timespec>,
+        _bkuptime: Option<Timespec>,
+        _flags: Option<u32>,
+        reply: ReplyAttr,
+    ) {
+        let op = Op::Setattr {
+            ino: ino,
+            size: size,
+        };
+        trace::run(self, req, op).send_attr(&TTL, reply)
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request,
+        _ino: u64,
+        fh: u64,
+        _flags: u32,
+        _lock_owner: u64,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        self.release_stream(fh);
+        reply.ok();
+    }
+
+    fn flush(&mut self, _req: &Request, _ino: u64, _fh: u64, _lock_owner: u64, reply: ReplyEmpty) {
+        reply.ok();
+    }
+
+    fn getxattr(&mut self, _req: &Request, ino: u64, name: &OsStr, size: u32, reply: ReplyXattr) {
+        match self.game.xattrs(ino) {
+            Ok(xattrs) => match xattrs.iter().find(|(n, _)| OsStr::new(n) == name) {
+                Some((_, value)) => xattrs::reply_value(value.as_bytes(), size, reply),
+                None => reply.error(ENODATA),
+            },
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn listxattr(&mut self, _req: &Request, ino: u64, size: u32, reply: ReplyXattr) {
+        match self.game.xattrs(ino) {
+            Ok(xattrs) => {
+                let mut names = Vec::new();
+                for (name, _) in xattrs.iter() {
+                    names.extend_from_slice(name.as_bytes());
+                    names.push(0);
+                }
+                xattrs::reply_value(&names, size, reply)
+            }
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn setxattr(
+        &mut self,
+        _req: &Request,
+        _ino: u64,
+        name: &OsStr,
+        _value: &[u8],
+        _flags: u32,
+        _position: u32,
+        reply: ReplyEmpty,
+    ) {
+        reply.error(fuse_game::xattrs::read_only(name));
+    }
+
+    fn removexattr(&mut self, _req: &Request, _ino: u64, name: &OsStr, reply: ReplyEmpty) {
+        reply.error(fuse_game::xattrs::read_only(name));
+    }
+}
+
+fn main() {
+    let args = match args::parse(env::args_os().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("fuschia: {}\n{}", e, args::USAGE);
+            process::exit(2);
+        }
+    };
+    let header = match args.trace.header(2) {
+        Ok(header) => header,
+        Err(e) => {
+            eprintln!("fuschia: {}", e);
+            process::exit(1);
+        }
+    };
+    // a new game starts when the trace says it did
+    let game = speedrun::stopped_at(header.started_at, || {
+        match (&args.resume, args.seed, &args.level_file) {
+            (Some(save_file), _, _) => save::load(save_file),
+            (None, Some(seed), _) => {
+                Ok(Game::new(generate::generate(seed, &args.generator).build()))
+            }
+            (None, None, Some(level_file)) => level::load(Path::new(level_file)).map(|l| l.game()),
+            (None, None, None) => level::parse(level::DEFAULT_LEVEL).map(|l| l.game()),
+        }
+    });
+    let mut game = match game {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("fuschia: {}", e);
+            process::exit(1);
+        }
+    };
+    game.save_file = args.save;
+    game.needs = args.needs;
+    // the first seed is for wandering kittens, the second for dogs
+    game.wander = Wander::new(args.wandering, header.seeds[0]);
+    game.dogs = Pack::new(args.dogs, header.seeds[1]);
+    game.generator = args.generator;
+    let mut fs = FuschiaFS::new(game);
+
+    if let Some(ref trace_file) = args.trace.replay {
+        // a replay mustn't touch anybody's save
+        fs.game.save_file = None;
+        trace::replay_and_exit(&mut fs, trace_file);
+    }
+    fs.recorder = match args.trace.recorder(&header) {
+        Ok(recorder) => recorder,
+        Err(e) => {
+            eprintln!("fuschia: {}", e);
+            process::exit(1);
+        }
+    };
+
+    env_logger::init();
+    // only --replay goes without one
+    let mountpoint = args.mountpoint.unwrap();
+    let options = ["-o", "rw", "-o", "fsname=hello"]
+        .iter()
+        .map(|o| o.as_ref())
+        .collect::<Vec<&OsStr>>();
+    fuse::mount(fs, &mountpoint, &options).unwrap();
+}
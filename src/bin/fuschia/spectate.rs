@@ -0,0 +1,89 @@
+// `.events`, for watching a game live.
+
+use libc::{c_int, EINTR, EIO};
+use serde_json;
+use std::cmp;
+use std::collections::HashMap;
+
+use FuschiaFS;
+
+// answers a read with what it read, or an errno
+type Answer = Box<dyn FnOnce(Result<&[u8], c_int>)>;
+
+struct Cursor {
+    // the next event this reader hasn't seen
+    next: usize,
+    // what's left of a line that didn't fit in the last read
+    unread: Vec<u8>,
+    // reads waiting for something to happen, and how much they wanted
+    waiting: Vec<(u32, Answer)>,
+}
+
+#[derive(Default)]
+pub struct Spectators {
+    cursors: HashMap<u64, Cursor>,
+    last_fh: u64,
+}
+
+impl FuschiaFS {
+    // a new cursor on `.events`, returned as a file handle
+    pub fn open_stream(&mut self) -> u64 {
+        let spectators = &mut self.spectators;
+        spectators.last_fh += 1;
+        spectators.cursors.insert(
+            spectators.last_fh,
+            Cursor {
+                next: self.game.events().len(),
+                unread: Vec::new(),
+                waiting: Vec::new(),
+            },
+        );
+        spectators.last_fh
+    }
+
+    // parks the read until there's something for it. fuse 0.3 doesn't pass
+    // interrupts on, so a killed `cat` only goes away at the next event
+    pub fn read_stream<F: FnOnce(Result<&[u8], c_int>) + 'static>(
+        &mut self,
+        fh: u64,
+        size: u32,
+        answer: F,
+    ) {
+        match self.spectators.cursors.get_mut(&fh) {
+            Some(cursor) => cursor.waiting.push((size, Box::new(answer))),
+            None => return answer(Err(EIO)),
+        }
+        self.wake_spectators();
+    }
+
+    // answers every waiting read that has something to read
+    pub fn wake_spectators(&mut self) {
+        let events = self.game.events();
+        for cursor in self.spectators.cursors.values_mut() {
+            while !cursor.waiting.is_empty() {
+                if cursor.unread.is_empty() {
+                    let event = match events.get(cursor.next) {
+                        Some(event) => event,
+                        None => break,
+                    };
+                    cursor.next += 1;
+                    // a LoggedEvent is always valid JSON
+                    cursor.unread = serde_json::to_vec(event).unwrap();
+                    cursor.unread.push(b'\n');
+                }
+                let (size, answer) = cursor.waiting.remove(0);
+                let end = cmp::min(cursor.unread.len(), size as usize);
+                answer(Ok(&cursor.unread[..end]));
+                cursor.unread.drain(..end);
+            }
+        }
+    }
+
+    pub fn release_stream(&mut self, fh: u64) {
+        if let Some(cursor) = self.spectators.cursors.remove(&fh) {
+            for (_, answer) in cursor.waiting {
+                answer(Err(EINTR));
+            }
+        }
+    }
+}
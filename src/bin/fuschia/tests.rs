@@ -0,0 +1,681 @@
+use fuse::consts::FOPEN_DIRECT_IO;
+use fuse_game::dogs::{Dogs, Pack};
+use fuse_game::driver::{self, Driver};
+use fuse_game::trace::{self, Caller, Header, Op, Ops, Reply};
+use fuse_game::wander::{Wander, Wandering};
+use fuse_game::{speedrun, Game};
+use libc::{c_int, EACCES, EAGAIN, EINTR, EINVAL, ENOENT, ENOTSUP, EPERM, O_RDONLY, S_IFIFO};
+use serde_json::json;
+use std::cell::RefCell;
+use std::ffi::OsStr;
+use std::rc::Rc;
+use std::{env, fs, process};
+
+use FuschiaFS;
+
+// `game`, mounted
+fn mount(game: Game) -> Driver<FuschiaFS> {
+    Driver::new(FuschiaFS::new(game))
+}
+
+#[test]
+fn lists_the_default_level() {
+    let mut driver = mount(driver::starcon());
+    assert_eq!(
+        driver.list("/").unwrap(),
+        vec![
+            "xxx",
+            "lskdjf",
+            ".fuschia",
+            "LiveJournal.txt",
+            "leaderboard.txt",
+            "events.log",
+            ".events",
+            "3.txt",
+        ]
+    );
+    assert_eq!(driver.list("xxx").unwrap(), vec!["xxxxx", "5.txt", "6.txt"]);
+    assert_eq!(driver.list("nope"), Err(ENOENT));
+}
+
+#[test]
+fn the_log_is_as_big_as_it_says() {
+    let mut driver = mount(driver::play(
+        r#"{
+          "name": "house",
+          "journal": true,
+          "kittens": [{ "name": "cat.txt" }],
+          "dirs": [
+            { "name": "a" }, { "name": "b" }, { "name": "c" },
+            { "name": "d" }, { "name": "e" }, { "name": "f" }
+          ]
+        }"#,
+    ));
+    // a dog turns up (and gets logged) on every operation that ticks
+    let every_op = Dogs {
+        every_secs: None,
+        every_ops: Some(1),
+    };
+    driver.fs.game.dogs = Pack::new(every_op, 7);
+    let caller = driver.caller;
+    let ino = driver.lookup_path("events.log").unwrap().ino;
+    for _ in 0..3 {
+        let size = match driver.fs.getattr(&caller, ino) {
+            Reply::Attr(attr) => attr.size,
+            other => panic!("{:?}", other),
+        };
+        match driver.fs.read(&caller, ino, 0, 0, 4096) {
+            Reply::Data(data) => assert_eq!(data.len() as u64, size),
+            other => panic!("{:?}", other),
+        }
+    }
+}
+
+#[test]
+fn spectators_get_each_event_as_it_happens() {
+    let mut driver = mount(driver::starcon());
+    let caller = driver.caller;
+    let ino = driver.lookup_path(".events").unwrap().ino;
+    let fh = match driver.fs.open(&caller, ino, O_RDONLY as u32) {
+        Reply::Opened { fh, .. } => fh,
+        other => panic!("{:?}", other),
+    };
+    let got = Rc::new(RefCell::new(Vec::new()));
+    let read = |driver: &mut Driver<FuschiaFS>, size| {
+        let got = Rc::clone(&got);
+        driver.fs.read_stream(fh, size, move |data| {
+            let data = data.map(|d| String::from_utf8_lossy(d).into_owned());
+            got.borrow_mut().push(data)
+        });
+    };
+
+    // nothing's happened yet, so the read waits
+    read(&mut driver, 4096);
+    assert!(got.borrow().is_empty());
+    driver.pet("3.txt", 1);
+    {
+        let got = got.borrow();
+        assert_eq!(got.len(), 1);
+        let line = got[0].as_ref().unwrap();
+        assert!(line.contains("3.txt") && line.ends_with("}\n"));
+    }
+
+    // a short read gets the rest of the line next time
+    driver.pet("3.txt", 1);
+    read(&mut driver, 5);
+    read(&mut driver, 4096);
+    {
+        let got = got.borrow();
+        assert_eq!(got[1].as_ref().unwrap().len(), 5);
+        let line = format!("{}{}", got[1].as_ref().unwrap(), got[2].as_ref().unwrap());
+        assert!(line.contains("3.txt") && line.ends_with("}\n"));
+    }
+
+    // closing the file gives up on a read that's still waiting
+    read(&mut driver, 4096);
+    driver.fs.release_stream(fh);
+    assert_eq!(got.borrow().last(), Some(&Err(EINTR)));
+}
+
+#[test]
+fn petting_a_kitten_to_peace() {
+    let mut driver = mount(driver::starcon());
+    assert!(driver
+        .read_path("3.txt")
+        .unwrap()
+        .contains("Please send me 5 pets"));
+    driver.pet("3.txt", 5);
+    assert!(driver
+        .read_path("3.txt")
+        .unwrap()
+        .contains("YOU GAVE ME ENOUGH PETS"));
+    let journal = driver.read_path("LiveJournal.txt").unwrap();
+    assert!(journal.contains("* 4 kitties still need pets"));
+    assert!(journal.contains("* 1 kitties are at peace with the world"));
+}
+
+#[test]
+fn petting_too_much_makes_a_kitten_mad() {
+    let mut driver = mount(driver::starcon());
+    driver.pet("xxx/5.txt", 6);
+    let journal = driver.read_path("LiveJournal.txt").unwrap();
+    assert!(journal.contains("* 1 kitties are mad because I petted them too much!"));
+}
+
+#[test]
+fn kittens_only_take_what_they_want() {
+    let mut driver = mount(driver::starcon());
+    assert_eq!(driver.write_path("3.txt", "treats\n"), Err(ENOTSUP));
+    assert_eq!(driver.write_path("LiveJournal.txt", "pets\n"), Err(EACCES));
+    assert_eq!(driver.write_path("4.txt", "pets\n"), Err(ENOENT));
+}
+
+#[test]
+fn locked_rooms_open_when_the_room_outside_is_at_peace() {
+    let mut driver = mount(driver::starcon());
+    assert_eq!(driver.list("xxx/xxxxx"), Err(EACCES));
+    driver.pet("xxx/5.txt", 5);
+    driver.pet("xxx/6.txt", 5);
+    assert_eq!(driver.list("xxx/xxxxx").unwrap(), vec!["8.txt"]);
+}
+
+const HOUSE: &str = r#"{
+    "name": "house",
+    "kittens": [{ "name": "cat.txt", "life": 2 }, { "name": "calm.txt", "life": 0 }],
+    "dirs": [{ "name": "den" }]
+}"#;
+
+const EVERY_OP: Wandering = Wandering {
+    every_secs: None,
+    every_ops: Some(1),
+};
+
+fn control(driver: &mut Driver<FuschiaFS>, command: &str) -> Result<u32, c_int> {
+    driver.write_path(".fuschia/control", &format!("{}\n", command))
+}
+
+#[test]
+fn reset_starts_over_with_every_kitten_back_home() {
+    let mut driver = mount(driver::play(HOUSE));
+    driver.fs.game.wander = Wander::new(EVERY_OP, 7);
+    driver.list("/").unwrap();
+    assert_eq!(driver.list("den").unwrap(), vec!["cat.txt"]);
+    driver.pet("den/cat.txt", 1);
+    driver.pet("calm.txt", 1);
+    assert_eq!(driver.fs.game.status().tally.mad, 1);
+
+    // stop wandering, so the kitten stays home once it's there
+    driver.fs.game.wander = Wander::new(Wandering::default(), 7);
+    control(&mut driver, "reset").unwrap();
+    assert!(driver.list("den").unwrap().is_empty());
+    assert!(driver
+        .read_path("cat.txt")
+        .unwrap()
+        .contains("Please send me 2 pets"));
+    assert!(driver
+        .read_path("calm.txt")
+        .unwrap()
+        .contains("YOU GAVE ME ENOUGH PETS"));
+    let status = driver.fs.game.status();
+    assert_eq!((status.tally.needing_pets, status.tally.mad), (1, 0));
+}
+
+#[test]
+fn new_swaps_in_a_generated_level() {
+    let mut driver = mount(driver::starcon());
+    let caller = driver.caller;
+    let kitten = driver.lookup_path("3.txt").unwrap().ino;
+    control(&mut driver, "new 42").unwrap();
+    assert_eq!(driver.lookup_path("xxx"), Err(ENOENT));
+    assert_eq!(
+        driver.fs.read(&caller, kitten, 0, 0, 4096),
+        Reply::Error(ENOENT)
+    );
+    let listed = driver.list("/").unwrap();
+    assert!(listed.contains(&".fuschia".to_string()));
+    assert!(listed.contains(&"LiveJournal.txt".to_string()));
+
+    // the same seed makes the same level
+    control(&mut driver, "new 42").unwrap();
+    assert_eq!(driver.list("/").unwrap(), listed);
+    assert_eq!(control(&mut driver, "new fish"), Err(EINVAL));
+}
+
+#[test]
+fn difficulty_changes_how_much_care_kittens_need() {
+    let mut driver = mount(driver::starcon());
+    control(&mut driver, "difficulty easy").unwrap();
+    assert!(driver
+        .read_path("3.txt")
+        .unwrap()
+        .contains("Please send me 3 pets"));
+    control(&mut driver, "difficulty hard").unwrap();
+    assert!(driver
+        .read_path("3.txt")
+        .unwrap()
+        .contains("Please send me 10 pets"));
+    assert!(driver
+        .read_path(".fuschia/control")
+        .unwrap()
+        .contains("difficulty: hard"));
+
+    // and on hard, more than 2 at once is too much
+    driver.write_path("3.txt", "pets\npets\npets\n").unwrap();
+    assert!(driver.read_path("3.txt").unwrap().contains("NO MORE PETS"));
+    assert_eq!(control(&mut driver, "difficulty impossible"), Err(EINVAL));
+}
+
+#[test]
+fn pausing_stops_every_clock() {
+    let start = 1_500_000_000_000;
+    let at = |secs: i64| start + secs * 1000;
+    let write_at = |driver: &mut Driver<FuschiaFS>, secs, path: &str, data: &str| {
+        speedrun::stopped_at(at(secs), || driver.write_path(path, data))
+    };
+    let mut driver = speedrun::stopped_at(at(0), || mount(driver::impatient("hiss")));
+    driver.fs.game.needs.lonely_after = Some(60);
+
+    write_at(&mut driver, 0, "cat", "pets\n").unwrap();
+    write_at(&mut driver, 10, ".fuschia/control", "pause\n").unwrap();
+    assert!(driver
+        .read_path(".fuschia/control")
+        .unwrap()
+        .contains("paused: yes"));
+    // an hour later nobody's lonely
+    let content = speedrun::stopped_at(at(3600), || driver.read_path("cat").unwrap());
+    assert!(content.contains("Please send me 4 pets"));
+
+    // and only 10 seconds have gone by as far as the cooldown's concerned
+    write_at(&mut driver, 3600, ".fuschia/control", "resume\n").unwrap();
+    assert_eq!(write_at(&mut driver, 3600, "cat", "pets\n"), Err(EAGAIN));
+    assert_eq!(write_at(&mut driver, 3650, "cat", "pets\n"), Ok(5));
+}
+
+#[test]
+fn pets_while_paused_are_timed_by_the_game_clock() {
+    let start = 1_500_000_000_000;
+    let at = |secs: i64| start + secs * 1000;
+    let write_at = |driver: &mut Driver<FuschiaFS>, secs, path: &str, data: &str| {
+        speedrun::stopped_at(at(secs), || driver.write_path(path, data))
+    };
+    let mut driver = speedrun::stopped_at(at(0), || mount(driver::impatient("hiss")));
+
+    write_at(&mut driver, 0, "cat", "pets\n").unwrap();
+    write_at(&mut driver, 10, ".fuschia/control", "pause\n").unwrap();
+    // a minute later on the wall, but only 10 seconds into the game
+    assert_eq!(write_at(&mut driver, 71, "cat", "pets\n"), Err(EAGAIN));
+    write_at(&mut driver, 3600, ".fuschia/control", "resume\n").unwrap();
+    assert_eq!(write_at(&mut driver, 3700, "cat", "pets\n"), Ok(5));
+}
+
+#[test]
+fn pets_are_credited_to_whoever_sent_them() {
+    let mut driver = mount(driver::starcon()).caller(Caller {
+        uid: 4242,
+        gid: 100,
+        pid: 7,
+    });
+    driver.pet("3.txt", 1);
+    assert!(driver
+        .read_path("events.log")
+        .unwrap()
+        .contains("3.txt got pets from uid 4242"));
+}
+
+#[test]
+fn scripts_decide_what_kittens_do() {
+    let mut driver = mount(driver::scripted(
+        r#"
+        fn on_pet(kitten, verb) { 0 }
+        fn on_read(kitten) { kitten.name + " needs " + kitten.life + "\n" }
+        "#,
+    ));
+    assert_eq!(driver.read_path("cat").unwrap(), "cat needs 5\n");
+    driver.write_path("cat", "pets\n").unwrap();
+    assert_eq!(driver.read_path("cat").unwrap(), "cat needs 0\n");
+    assert!(driver
+        .read_path("LiveJournal.txt")
+        .unwrap()
+        .contains("All the kitties are at peace!!!"));
+}
+
+#[test]
+fn broken_scripts_show_up_in_the_kitten() {
+    let mut driver = mount(driver::scripted(
+        "fn on_pet(kitten, verb) { kitten.nope() }",
+    ));
+    driver.write_path("cat", "pets\n").unwrap();
+    let content = driver.read_path("cat").unwrap();
+    assert!(content.contains("Please send me 4 pets"));
+    assert!(content.contains("My script is broken :("));
+    assert!(content.contains("on_pet"));
+
+    let mut driver = mount(driver::scripted("fn on_read(kitten) { loop {} }"));
+    assert!(driver
+        .read_path("cat")
+        .unwrap()
+        .contains("My script is broken :("));
+}
+
+#[test]
+fn rushed_kittens_hiss() {
+    let mut driver = mount(driver::impatient("hiss"));
+    driver.write_path("cat", "pets\n").unwrap();
+    assert_eq!(driver.write_path("cat", "pets\n"), Err(EAGAIN));
+    let content = driver.read_path("cat").unwrap();
+    assert!(content.contains("Please send me 4 pets"));
+    assert!(content.contains("HSSSSSSS"));
+
+    let mut driver = mount(driver::impatient("hiss"));
+    assert_eq!(driver.write_path("cat", "pets\npets\n"), Err(EAGAIN));
+    assert!(driver
+        .read_path("cat")
+        .unwrap()
+        .contains("Please send me 5 pets"));
+}
+
+#[test]
+fn rushed_pets_can_count_double() {
+    let mut driver = mount(driver::impatient("double"));
+    driver.write_path("cat", "pets\n").unwrap();
+    driver.write_path("cat", "pets\n").unwrap();
+    assert!(driver
+        .read_path("cat")
+        .unwrap()
+        .contains("Please send me 2 pets"));
+}
+
+fn pet_as(driver: &mut Driver<FuschiaFS>, uid: u32, pid: u32) {
+    driver.caller = Caller {
+        uid: uid,
+        gid: 100,
+        pid: pid,
+    };
+    driver.write_path("boss.txt", "pets\n").unwrap();
+}
+
+#[test]
+fn bosses_need_a_team() {
+    let mut driver = mount(driver::boss("pid"));
+    assert!(driver
+        .read_path("boss.txt")
+        .unwrap()
+        .contains("0/3 helpers petting"));
+    pet_as(&mut driver, 1000, 1);
+    pet_as(&mut driver, 1000, 2);
+    pet_as(&mut driver, 1000, 2);
+    assert!(driver
+        .read_path("boss.txt")
+        .unwrap()
+        .contains("2/3 helpers petting"));
+    pet_as(&mut driver, 1000, 3);
+    assert!(driver
+        .read_path("LiveJournal.txt")
+        .unwrap()
+        .contains("All the kitties are at peace!!!"));
+}
+
+#[test]
+fn bosses_can_count_users_instead() {
+    let mut driver = mount(driver::boss("uid"));
+    pet_as(&mut driver, 1000, 1);
+    pet_as(&mut driver, 1000, 2);
+    pet_as(&mut driver, 1001, 3);
+    assert!(driver
+        .read_path("boss.txt")
+        .unwrap()
+        .contains("2/3 helpers petting"));
+}
+
+const ONE_ROOM: &str = r#"{ "name": "room", "kittens": [{ "name": "cat.txt", "life": 2 }] }"#;
+
+#[test]
+fn dogs_have_to_be_chased_off() {
+    let mut driver = mount(driver::play(ONE_ROOM));
+    driver.pet("cat.txt", 1);
+    driver.fs.game.dogs_arrive();
+    assert!(driver.list("/").unwrap().contains(&"dog.woof".to_string()));
+    assert_eq!(driver.write_path("dog.woof", "pets\n"), Err(EACCES));
+    let journal = driver.read_path("LiveJournal.txt").unwrap();
+    assert!(journal.contains("* 1 dog is scaring the kitties!"));
+    assert!(!journal.contains("GAME OVER"));
+
+    // there's only room for one dog, so this time it just barks
+    driver.fs.game.dogs_arrive();
+    assert!(driver
+        .read_path("cat.txt")
+        .unwrap()
+        .contains("Please send me 2 pets"));
+
+    assert_eq!(driver.fs.game.unlink(1, "dog.woof"), Ok(()));
+    driver.pet("cat.txt", 2);
+    let journal = driver.read_path("LiveJournal.txt").unwrap();
+    assert!(journal.contains("All the kitties are at peace!!!"));
+    assert!(journal.contains("Dogs chased off: 1"));
+}
+
+const STORY: &str = r#"{
+            "name": "home",
+            "story": "The kitties are restless tonight.",
+            "kittens": [{ "name": "3.txt", "life": 1 }],
+            "chapters": [{
+                "story": "Something is scratching behind the couch!",
+                "dir": { "name": "couch", "kittens": [{ "name": "7.txt", "life": 1 }] }
+            }]
+        }"#;
+
+#[test]
+fn chapters_turn_up_when_the_last_one_is_done() {
+    let mut driver = mount(driver::play(STORY));
+    assert_eq!(driver.lookup_path("couch"), Err(ENOENT));
+    let journal = driver.read_path("LiveJournal.txt").unwrap();
+    assert!(journal.contains("Chapter 1\n\nThe kitties are restless tonight."));
+    assert!(!journal.contains("Chapter 2"));
+
+    driver.pet("3.txt", 1);
+    assert_eq!(driver.list("couch").unwrap(), vec!["7.txt"]);
+    let journal = driver.read_path("LiveJournal.txt").unwrap();
+    assert!(journal.contains("Chapter 2\n\nSomething is scratching behind the couch!"));
+    assert!(journal.contains("* 1 kitties still need pets"));
+
+    driver.pet("couch/7.txt", 1);
+    assert!(driver
+        .read_path("LiveJournal.txt")
+        .unwrap()
+        .contains("All the kitties are at peace!!!"));
+
+    driver.write_path(".fuschia/control", "reset\n").unwrap();
+    assert_eq!(driver.lookup_path("couch"), Err(ENOENT));
+}
+
+#[test]
+fn lessons_watch_who_looked_and_who_petted() {
+    let mut driver = mount(driver::lesson("find_exec"));
+    driver.as_pid(10);
+    driver.list("/").unwrap();
+    driver.list("room").unwrap();
+    driver.as_pid(11);
+    driver.pet("a.txt", 1);
+    driver.as_pid(12);
+    driver.pet("room/b.txt", 1);
+    assert!(driver
+        .read_path("LiveJournal.txt")
+        .unwrap()
+        .contains("LESSON PASSED!!"));
+
+    let mut driver = mount(driver::lesson("find_exec"));
+    driver.as_pid(10);
+    driver.list("/").unwrap();
+    driver.list("room").unwrap();
+    driver.pet("a.txt", 1);
+    driver.pet("room/b.txt", 1);
+    assert!(driver
+        .read_path("LiveJournal.txt")
+        .unwrap()
+        .contains("Not quite: pid 10 looked everywhere but did some petting itself."));
+}
+
+#[test]
+fn lessons_count_lookups_and_wait_for_every_kitten_to_be_at_peace() {
+    // looking something up in a room is looking in it
+    let mut driver = mount(driver::lesson("find_exec"));
+    driver.as_pid(10);
+    driver.list("/").unwrap();
+    driver.lookup_path("room/b.txt").unwrap();
+    driver.as_pid(11);
+    driver.pet("a.txt", 1);
+    driver.as_pid(12);
+    driver.pet("room/b.txt", 1);
+    assert!(driver
+        .read_path("LiveJournal.txt")
+        .unwrap()
+        .contains("LESSON PASSED!!"));
+
+    // a mad kitten isn't at peace, so there's no verdict yet
+    let mut driver = mount(driver::lesson("find_exec"));
+    driver.as_pid(10);
+    driver.list("/").unwrap();
+    driver.list("room").unwrap();
+    driver.as_pid(11);
+    driver.pet("a.txt", 2);
+    driver.as_pid(12);
+    driver.pet("room/b.txt", 1);
+    let journal = driver.read_path("LiveJournal.txt").unwrap();
+    assert!(!journal.contains("LESSON PASSED!!"));
+    assert!(!journal.contains("Not quite"));
+}
+
+#[test]
+fn xargs_lessons_want_one_process_that_was_told_the_names() {
+    let mut driver = mount(driver::lesson("xargs"));
+    let journal = driver.read_path("LiveJournal.txt").unwrap();
+    assert!(journal.contains("Lesson: pet every kitty from one process"));
+    assert!(!journal.contains("LESSON PASSED"));
+    driver.as_pid(20);
+    driver.pet("a.txt", 1);
+    driver.pet("room/b.txt", 1);
+    assert!(driver
+        .read_path("LiveJournal.txt")
+        .unwrap()
+        .contains("LESSON PASSED!!"));
+
+    driver.write_path(".fuschia/control", "reset\n").unwrap();
+    driver.list("room").unwrap();
+    driver.pet("a.txt", 1);
+    driver.pet("room/b.txt", 1);
+    assert!(driver
+        .read_path("LiveJournal.txt")
+        .unwrap()
+        .contains("Not quite: pid 20 went looking for kitties instead of being told."));
+}
+
+#[test]
+fn adopting_and_opening_are_replayed_too() {
+    let mut driver = mount(driver::starcon());
+    let control_dir = driver.fs.game.resolve(".fuschia").unwrap();
+    let journal = driver.fs.game.resolve("LiveJournal.txt").unwrap();
+    let records = vec![
+        (
+            json!({ "open": { "ino": journal, "flags": O_RDONLY } }),
+            json!({ "opened": { "fh": 0, "flags": FOPEN_DIRECT_IO } }),
+        ),
+        (
+            json!({ "setattr": { "ino": journal, "size": 0 } }),
+            json!({ "error": EACCES }),
+        ),
+        (
+            json!({ "create": { "parent": control_dir, "name": "cat.txt", "mode": 0o644, "flags": 0 } }),
+            json!({ "error": EACCES }),
+        ),
+        (
+            json!({ "mknod": { "parent": 1, "name": "pipe", "mode": S_IFIFO, "rdev": 0 } }),
+            json!({ "error": EPERM }),
+        ),
+        (
+            json!({ "unlink": { "parent": 1, "name": "nope.txt" } }),
+            json!({ "error": ENOENT }),
+        ),
+        (
+            json!({ "unlink": { "parent": 1, "name": "3.txt" } }),
+            json!("empty"),
+        ),
+    ];
+    let path = env::temp_dir().join(format!("fuschia-test-{}.trace", process::id()));
+    let mut trace = format!("{}\n", json!({ "started_at": 0, "seeds": [] }));
+    for (op, reply) in records {
+        let record = json!({
+            "at": 0,
+            "caller": { "uid": 1000, "gid": 100, "pid": 1 },
+            "op": op,
+            "reply": reply,
+        });
+        trace.push_str(&format!("{}\n", record));
+    }
+    fs::write(&path, trace).unwrap();
+    let replayed = trace::replay(&mut driver.fs, &path);
+    fs::remove_file(&path).unwrap();
+    let (count, mismatches) = replayed.unwrap();
+    assert_eq!(count, 6);
+    if let Some(mismatch) = mismatches.first() {
+        panic!("{}", mismatch);
+    }
+    assert_eq!(driver.read_path("3.txt"), Err(ENOENT));
+}
+
+#[test]
+fn replays_happen_when_and_how_they_were_recorded() {
+    let header = Header {
+        started_at: 1_500_000_000_000,
+        seeds: vec![7, 8],
+    };
+    let every_op = Wandering {
+        every_secs: None,
+        every_ops: Some(1),
+    };
+    let new_fs = || {
+        let mut fs = speedrun::stopped_at(header.started_at, || FuschiaFS::new(driver::starcon()));
+        fs.game.needs.lonely_after = Some(60);
+        fs.game.wander = Wander::new(every_op, header.seeds[0]);
+        fs.game.dogs = Pack::new(every_op, header.seeds[1]);
+        fs
+    };
+
+    // five pets to bring a kitten to peace, then a minute between requests:
+    // long enough for it to get lonely again
+    let mut fs = new_fs();
+    let caller = Caller {
+        uid: 1000,
+        gid: 100,
+        pid: 1,
+    };
+    let kitten = fs.game.resolve("3.txt").unwrap();
+    let mut trace = format!("{}\n", serde_json::to_string(&header).unwrap());
+    for i in 0..14 {
+        let at = header.started_at + i * 60_000;
+        let (op, reply) = speedrun::stopped_at(at, || match i % 3 {
+            _ if i < 5 => (
+                Op::Write {
+                    ino: kitten,
+                    fh: 0,
+                    offset: 0,
+                    data: b"pets\n".to_vec(),
+                    flags: 0,
+                },
+                fs.write(&caller, kitten, 0, 0, b"pets\n", 0),
+            ),
+            0 => (
+                Op::Readdir {
+                    ino: 1,
+                    fh: 0,
+                    offset: 0,
+                },
+                fs.readdir(&caller, 1, 0, 0),
+            ),
+            1 => (
+                Op::lookup(1, OsStr::new("3.txt")),
+                fs.lookup(&caller, 1, OsStr::new("3.txt")),
+            ),
+            _ => (
+                Op::Read {
+                    ino: kitten,
+                    fh: 0,
+                    offset: 0,
+                    size: 4096,
+                },
+                fs.read(&caller, kitten, 0, 0, 4096),
+            ),
+        });
+        let record = json!({ "at": at, "caller": caller, "op": op, "reply": reply });
+        trace.push_str(&format!("{}\n", record));
+    }
+
+    let path = env::temp_dir().join(format!("fuschia-test-{}-clock.trace", process::id()));
+    fs::write(&path, trace).unwrap();
+    let replayed = trace::replay(&mut new_fs(), &path);
+    fs::remove_file(&path).unwrap();
+    let (count, mismatches) = replayed.unwrap();
+    assert_eq!(count, 14);
+    if let Some(mismatch) = mismatches.first() {
+        panic!("{}", mismatch);
+    }
+}
@@ -0,0 +1,15 @@
+// Handing extended attributes to the kernel.
+
+use fuse::ReplyXattr;
+use libc::ERANGE;
+
+// a `size` of 0 means the kernel only wants to know how big `value` is
+pub fn reply_value(value: &[u8], size: u32, reply: ReplyXattr) {
+    if size == 0 {
+        reply.size(value.len() as u32);
+    } else if (size as usize) < value.len() {
+        reply.error(ERANGE);
+    } else {
+        reply.data(value);
+    }
+}
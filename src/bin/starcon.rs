@@ -2,6 +2,7 @@
 
 extern crate env_logger;
 extern crate fuse;
+extern crate fuse_game;
 extern crate libc;
 extern crate time;
 
@@ -9,9 +10,12 @@ use fuse::{
     FileAttr, FileType, Filesystem, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry, ReplyOpen,
     Request,
 };
-use libc::ENOENT;
+use fuse_game::read_slice;
+use fuse_game::trace::{self, Caller, Header, Op, Ops, Recorder, Reply, TraceArgs};
+use libc::{ENOENT, ENOSYS};
 use std::env;
 use std::ffi::OsStr;
+use std::process;
 use time::Timespec;
 
 const TTL: Timespec = Timespec { sec: 2, nsec: 0 }; // 1 second
@@ -70,67 +74,205 @@ const ROOT_FILE_ATTR: FileAttr = FileAttr {
 
 pub struct StarConFS {
     count: u64,
+    // for --record
+    recorder: Option<Recorder>,
 }
 
-impl Filesystem for StarConFS {
-    fn lookup(&mut self, _req: &Request, _parent: u64, _name: &OsStr, reply: ReplyEntry) {
-        reply.entry(&TTL, &starcon_file_attr(self.count), 0)
+impl Ops for StarConFS {
+    fn lookup(&mut self, _caller: &Caller, _parent: u64, _name: &OsStr) -> Reply {
+        Reply::entry(starcon_file_attr(self.count))
     }
 
-    fn getattr(&mut self, _req: &Request, ino: u64, reply: ReplyAttr) {
+    fn getattr(&mut self, _caller: &Caller, ino: u64) -> Reply {
         match ino {
-            1 => reply.attr(&TTL, &ROOT_FILE_ATTR),
-            3 => reply.attr(&TTL, &starcon_file_attr(self.count)),
-            _ => reply.error(ENOENT),
+            1 => Reply::attr(ROOT_FILE_ATTR),
+            3 => Reply::attr(starcon_file_attr(self.count)),
+            _ => Reply::Error(ENOENT),
         }
     }
 
-    fn open(&mut self, _req: &Request, _ino: u64, _flags: u32, reply: ReplyOpen) {
-        self.count += 1;
-        reply.opened(_ino, 0)
-    }
-
-    fn read(
-        &mut self,
-        _req: &Request,
-        ino: u64,
-        _fh: u64,
-        offset: i64,
-        _size: u32,
-        reply: ReplyData,
-    ) {
+    fn read(&mut self, _caller: &Caller, ino: u64, _fh: u64, offset: i64, size: u32) -> Reply {
         if ino == 3 {
-            reply.data(&starcon_content(self.count).as_bytes()[offset as usize..])
+            Reply::data(read_slice(
+                starcon_content(self.count).as_bytes(),
+                offset,
+                size,
+            ))
         } else {
-            reply.error(ENOENT)
+            Reply::Error(ENOENT)
         }
     }
 
-    fn readdir(
+    fn readdir(&mut self, _caller: &Caller, _ino: u64, _fh: u64, offset: i64) -> Reply {
+        let entries = vec![
+            (11, FileType::Directory, "."),
+            (12, FileType::Directory, ".."),
+            (3, FileType::RegularFile, "starcon.txt"),
+        ];
+        Reply::Entries(trace::list_from(entries, offset))
+    }
+
+    // there's nothing to write to
+    fn write(
         &mut self,
-        _req: &Request,
+        _caller: &Caller,
         _ino: u64,
         _fh: u64,
-        offset: i64,
-        mut reply: ReplyDirectory,
-    ) {
-        let mut entries: Vec<(u64, FileType, &str)> = Vec::new();
-        entries.push((11, FileType::Directory, "."));
-        entries.push((12, FileType::Directory, ".."));
-        entries.push((3, FileType::RegularFile, "starcon.txt"));
-        let to_skip = if offset == 0 { offset } else { offset + 1 } as usize;
-        for (i, entry) in entries.into_iter().enumerate().skip(to_skip) {
-            reply.add(entry.0, i as i64, entry.1, entry.2);
-        }
-        reply.ok();
+        _offset: i64,
+        _data: &[u8],
+        _flags: u32,
+    ) -> Reply {
+        Reply::Error(ENOSYS)
+    }
+
+    // every open changes what the file says
+    fn open(&mut self, _caller: &Caller, ino: u64, _flags: u32) -> Reply {
+        self.count += 1;
+        Reply::Opened { fh: ino, flags: 0 }
+    }
+
+    fn recorder(&mut self) -> Option<&mut Recorder> {
+        self.recorder.as_mut()
     }
 }
 
+impl Filesystem for StarConFS {
+    fn lookup(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        trace::run(self, req, Op::lookup(parent, name)).send_entry(&TTL, reply)
+    }
+
+    fn getattr(&mut self, req: &Request, ino: u64, reply: ReplyAttr) {
+        trace::run(self, req, Op::Getattr { ino: ino }).send_attr(&TTL, reply)
+    }
+
+    fn open(&mut self, req: &Request, ino: u64, flags: u32, reply: ReplyOpen) {
+        let op = Op::Open {
+            ino: ino,
+            flags: flags,
+        };
+        trace::run(self, req, op).send_opened(reply)
+    }
+
+    fn read(&mut self, req: &Request, ino: u64, fh: u64, offset: i64, size: u32, reply: ReplyData) {
+        let op = Op::Read {
+            ino: ino,
+            fh: fh,
+            offset: offset,
+            size: size,
+        };
+        trace::run(self, req, op).send_data(reply)
+    }
+
+    fn readdir(&mut self, req: &Request, ino: u64, fh: u64, offset: i64, reply: ReplyDirectory) {
+        let op = Op::Readdir {
+            ino: ino,
+            fh: fh,
+            offset: offset,
+        };
+        trace::run(self, req, op).send_entries(reply)
+    }
+}
+
+const USAGE: &str = "usage: starcon <mountpoint> [--record <trace>]
+       starcon --replay <trace>";
+
 fn main() {
-    let mountpoint = env::args_os().nth(1).unwrap();
+    let (trace_args, rest) = match TraceArgs::parse(env::args_os().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("starcon: {}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    let mut fs = StarConFS {
+        count: 0,
+        recorder: None,
+    };
+    if let Some(ref trace_file) = trace_args.replay {
+        trace::replay_and_exit(&mut fs, trace_file);
+    }
+    // starcon doesn't roll any dice
+    fs.recorder = match trace_args.recorder(&Header::now(0)) {
+        Ok(recorder) => recorder,
+        Err(e) => {
+            eprintln!("starcon: {}", e);
+            process::exit(1);
+        }
+    };
+    let mountpoint = match rest.into_iter().next() {
+        Some(mountpoint) => mountpoint,
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
     let options = ["-o", "rw", "-o", "fsname=hello"]
         .iter()
         .map(|o| o.as_ref())
         .collect::<Vec<&OsStr>>();
-    fuse::mount(StarConFS { count: 0 }, &mountpoint, &options).unwrap();
+    fuse::mount(fs, &mountpoint, &options).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use fuse_game::driver::Driver;
+    use fuse_game::trace::{Ops, Reply};
+    use libc::ENOSYS;
+
+    use StarConFS;
+
+    // a fresh starcon, mounted
+    fn mount() -> Driver<StarConFS> {
+        Driver::new(StarConFS {
+            count: 0,
+            recorder: None,
+        })
+    }
+
+    #[test]
+    fn has_one_file() {
+        let mut driver = mount();
+        assert_eq!(driver.list("/").unwrap(), vec!["starcon.txt"]);
+        assert_eq!(driver.read_path("starcon.txt").unwrap(), "FUSE rocks!\n");
+    }
+
+    #[test]
+    fn changes_its_mind_every_open() {
+        let mut driver = mount();
+        let caller = driver.caller;
+        driver.fs.open(&caller, 3, 0);
+        assert_eq!(driver.read_path("starcon.txt").unwrap(), "Hello StarCon!\n");
+    }
+
+    #[test]
+    fn listings_pick_up_where_they_left_off() {
+        let mut driver = mount();
+        let caller = driver.caller;
+        let names = |reply: Reply| match reply {
+            Reply::Entries(entries) => entries.into_iter().map(|e| (e.offset, e.name)).collect(),
+            other => panic!("{:?}", other),
+        };
+        let all: Vec<(i64, String)> = names(driver.fs.readdir(&caller, 1, 0, 0));
+        assert_eq!(all.len(), 3);
+        let rest: Vec<(i64, String)> = names(driver.fs.readdir(&caller, 1, 0, all[0].0));
+        assert_eq!(rest, all[1..].to_vec());
+        let none: Vec<(i64, String)> = names(driver.fs.readdir(&caller, 1, 0, all[2].0));
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn is_read_only() {
+        let mut driver = mount();
+        assert_eq!(driver.write_path("starcon.txt", "hi"), Err(ENOSYS));
+    }
+
+    #[test]
+    fn reads_past_the_end_come_back_empty() {
+        let mut driver = mount();
+        let caller = driver.caller;
+        match driver.fs.read(&caller, 3, 0, 1000, 4096) {
+            Reply::Data(data) => assert!(data.is_empty()),
+            other => panic!("{:?}", other),
+        }
+    }
 }
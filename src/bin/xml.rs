@@ -2,469 +2,214 @@
 
 extern crate env_logger;
 extern crate fuse;
+extern crate fuse_game;
 extern crate libc;
 extern crate roxmltree;
 extern crate time;
 
 use fuse::{
-    FileAttr, FileType, Filesystem, ReplyAttr, ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry,
-    ReplyWrite, Request,
+    Filesystem, ReplyAttr, ReplyCreate, ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry,
+    ReplyOpen, ReplyWrite, Request,
 };
-use libc::ENOENT;
+use fuse_game::level::{DirSpec, KittenSpec};
+use fuse_game::ops;
+use fuse_game::speedrun;
+use fuse_game::trace::{self, Caller, Op, Ops, Recorder, Reply, TraceArgs};
+use fuse_game::{Game, JOURNAL};
 use roxmltree::{Document, Edge, Node};
-use std::collections::HashMap;
 use std::env;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::Read;
+use std::process;
 use time::Timespec;
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub enum GameEntity {
-    Directory {
-        inode: u64,
-        name: String,
-        parent: Option<u64>,
-        children: Vec<u64>,
-    },
-    File {
-        inode: u64,
-        name: String,
-        parent: Option<u64>,
-        content: String,
-        life: i32,
-        pets_needed: i32,
-    },
-}
-impl GameEntity {
-    pub fn dir(inode: u64, name: &str) -> GameEntity {
-        GameEntity::Directory {
-            inode: inode,
-            name: name.to_string(),
-            parent: None,
-            children: Vec::new(),
-        }
-    }
-    pub fn file(inode: u64, name: &str, content: &str) -> GameEntity {
-        GameEntity::File {
-            inode: inode,
-            name: name.to_string(),
-            parent: None,
-            content: content.to_string(),
-            life: 100,
-            pets_needed: 5,
-        }
-    }
-    pub fn get_name(&self) -> &str {
-        match self {
-            GameEntity::Directory { inode: _, name, .. } => name.as_str(),
-            GameEntity::File { inode: _, name, .. } => name.as_str(),
-        }
-    }
-    pub fn get_inode(&self) -> u64 {
-        match self {
-            GameEntity::Directory { inode, .. } => *inode,
-            GameEntity::File { inode, .. } => *inode,
-        }
-    }
-    pub fn get_content(&self) -> String {
-        match self {
-            GameEntity::File {
-                inode: _,
-                name: _,
-                parent: _,
-                content,
-                life: _,
-                pets_needed: _,
-            } => content.clone() + "\n",
-            GameEntity::Directory { .. } => "".to_string(),
-        }
-    }
-    pub fn set_parent(&mut self, parent_inode: u64) {
-        match self {
-            GameEntity::Directory {
-                inode: _,
-                name: _,
-                ref mut parent,
-                ..
-            } => {
-                *parent = Some(parent_inode);
-            }
-            GameEntity::File {
-                inode: _,
-                name: _,
-                ref mut parent,
-                ..
-            } => *parent = Some(parent_inode),
-        }
-    }
-    pub fn push_child(&mut self, child_inode: u64) {
-        match self {
-            GameEntity::Directory {
-                inode: _,
-                name: _,
-                parent: _,
-                children,
-            } => children.push(child_inode),
-            _ => {}
-        }
-    }
-    pub fn to_file_attr(&self) -> FileAttr {
-        match self {
-            GameEntity::Directory {
-                inode,
-                name: _,
-                parent: _,
-                children: _,
-            } => FileAttr {
-                ino: *inode,
-                size: 0,
-                blocks: 1,
-                atime: CREATE_TIME,
-                mtime: CREATE_TIME,
-                ctime: CREATE_TIME,
-                crtime: CREATE_TIME,
-                kind: FileType::Directory,
-                perm: 0o644,
-                nlink: 1,
-                uid: 1000,
-                gid: 100,
-                rdev: 0,
-                flags: 0,
-            },
-            GameEntity::File {
-                inode,
-                name: _,
-                parent: _,
-                content: _,
-                life: _,
-                pets_needed: _,
-            } => {
-                let mut content_size;
-                if self.get_name() == "LiveJournal.txt" {
-                    // oh god such a hack because we don't
-                    // have access to the GameStatus here
-                    let fake_game_status = GameStatus {
-                        kitties_needing_pets: 100,
-                        kitties_at_peace: 100,
-                        kitties_mad: 100,
-                    };
-                    content_size = fake_game_status.to_content().len() as u64;
-                } else {
-                    content_size = self.get_content().len() as u64;
-                }
-                FileAttr {
-                    ino: *inode,
-                    size: content_size,
-                    blocks: 1,
-                    atime: CREATE_TIME,
-                    mtime: CREATE_TIME,
-                    ctime: CREATE_TIME,
-                    crtime: CREATE_TIME,
-                    kind: FileType::RegularFile,
-                    perm: 0o644,
-                    nlink: 1,
-                    uid: 1000,
-                    gid: 100,
-                    rdev: 0,
-                    flags: 0,
-                }
-            }
-        }
-    }
-}
-#[derive(Debug, Eq, Hash, PartialEq)]
-pub struct GameFile {
-    name: String,
-    inode: u64,
-    content: String,
-    life: i32,
-}
-impl GameFile {
-    pub fn new(inode: u64, name: String, content: String) -> GameFile {
-        GameFile {
-            name: name,
-            inode: inode,
-            content: content,
-            life: 5,
-        }
-    }
-    pub fn content(mut self, content: &str) -> Self {
-        self.content = content.to_string();
-        self
-    }
-    pub fn to_game_entity(&self) -> GameEntity {
-        GameEntity::File {
-            inode: self.inode,
-            name: self.name.clone(),
-            parent: None,
-            content: self.content.clone(),
-            life: self.life,
-            pets_needed: self.life,
-        }
-    }
-}
+const TTL: Timespec = Timespec { sec: 1, nsec: 0 }; // 1 second
 
-#[derive(Debug, Eq, Hash, PartialEq)]
-pub struct GameDir {
-    name: String,
-    inode: u64,
-    files: Vec<GameFile>,
-    sub_dirs: Vec<GameDir>,
+// the XML file as a game: elements are directories, elements with text are
+// kittens and the directory with a `<LiveJournal.txt>` in it keeps the
+// journal. The kittens follow the same rules as fuschia's.
+pub struct HelloFS {
+    game: Game,
+    // for --record
+    recorder: Option<Recorder>,
 }
-impl GameDir {
-    pub fn new(inode: u64, name: String) -> GameDir {
-        GameDir {
-            name: name,
-            inode: inode,
-            files: Vec::new(),
-            sub_dirs: Vec::new(),
-        }
-    }
-    pub fn with_file(mut self, file: GameFile) -> Self {
-        self.files.push(file);
-        self
+
+impl Ops for HelloFS {
+    fn lookup(&mut self, caller: &Caller, parent: u64, name: &OsStr) -> Reply {
+        ops::lookup(&mut self.game, caller, parent, name)
     }
-    pub fn with_dir(mut self, dir: GameDir) -> Self {
-        self.sub_dirs.push(dir);
-        self
+
+    fn getattr(&mut self, _caller: &Caller, ino: u64) -> Reply {
+        ops::getattr(&mut self.game, ino)
     }
-    pub fn to_game_entities(&self, parent: Option<u64>) -> Vec<GameEntity> {
-        let mut vec = Vec::new();
-        let mut children_vec = Vec::new();
-        for file in self.files.iter() {
-            children_vec.push(file.inode);
-        }
-        for subdir in self.sub_dirs.iter() {
-            children_vec.push(subdir.inode);
-        }
-        let root = GameEntity::Directory {
-            inode: self.inode,
-            name: self.name.clone(),
-            parent: parent,
-            children: children_vec,
-        };
-        vec.push(root);
-        for file in self.files.iter() {
-            let mut entity = file.to_game_entity();
-            entity.set_parent(self.inode);
-            vec.push(entity);
-        }
-        for subdir in self.sub_dirs.iter() {
-            let subdir_vec = subdir.to_game_entities(Some(self.inode));
-            vec.extend(subdir_vec);
-        }
-        vec
+
+    fn read(&mut self, _caller: &Caller, ino: u64, _fh: u64, offset: i64, size: u32) -> Reply {
+        ops::read(&mut self.game, ino, offset, size)
     }
-    pub fn to_entity_hash_map(&self) -> HashMap<u64, GameEntity> {
-        let mut hash_map = HashMap::new();
-        for entity in self.to_game_entities(None) {
-            hash_map.insert(entity.get_inode(), entity);
-        }
-        hash_map
+
+    fn readdir(&mut self, caller: &Caller, ino: u64, _fh: u64, offset: i64) -> Reply {
+        ops::readdir(&mut self.game, caller, ino, offset)
     }
-}
 
-pub struct GameStatus {
-    kitties_needing_pets: u32,
-    kitties_at_peace: u32,
-    kitties_mad: u32,
-}
-impl GameStatus {
-    pub fn to_content(&self) -> String {
-        format!(
-            r#"Dear Diary,
+    fn write(
+        &mut self,
+        caller: &Caller,
+        ino: u64,
+        _fh: u64,
+        _offset: i64,
+        data: &[u8],
+        _flags: u32,
+    ) -> Reply {
+        ops::write(&mut self.game, caller, ino, data)
+    }
 
-All my friends are at StarCon! :(
+    fn open(&mut self, _caller: &Caller, ino: u64, flags: u32) -> Reply {
+        ops::open(&mut self.game, ino, flags)
+    }
 
-I have to stay at home and pet these kitties :~(
+    fn create(
+        &mut self,
+        _caller: &Caller,
+        parent: u64,
+        name: &OsStr,
+        _mode: u32,
+        _flags: u32,
+    ) -> Reply {
+        ops::create(&mut self.game, parent, name)
+    }
 
-Here's what I've done so far:
+    fn mknod(
+        &mut self,
+        _caller: &Caller,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        _rdev: u32,
+    ) -> Reply {
+        ops::mknod(&mut self.game, parent, name, mode)
+    }
 
-* {} kitties still need pets
-* {} kitties are at peace with the world
-* {} kitties are mad because I petted them too much!
-"#,
-            self.kitties_needing_pets, self.kitties_at_peace, self.kitties_mad
-        )
+    fn unlink(&mut self, _caller: &Caller, parent: u64, name: &OsStr) -> Reply {
+        ops::unlink(&mut self.game, parent, name)
     }
-}
 
-pub fn game_status(inode_map: &HashMap<u64, GameEntity>) -> GameStatus {
-    let mut needing_pets_count: u32 = 0;
-    let mut at_peace_count: u32 = 0;
-    let mut mad_count: u32 = 0;
-    for pair in inode_map.iter() {
-        match pair.1 {
-            GameEntity::Directory { .. } => {}
-            GameEntity::File {
-                inode: _,
-                name,
-                parent: _,
-                content: _,
-                life: _,
-                pets_needed,
-            } => {
-                if name != "LiveJournal.txt" {
-                    if *pets_needed > 0 {
-                        needing_pets_count += 1;
-                    } else if *pets_needed == 0 {
-                        at_peace_count += 1;
-                    } else {
-                        mad_count += 1;
-                    }
-                }
-            }
-        }
+    fn setattr(&mut self, _caller: &Caller, ino: u64, size: Option<u64>) -> Reply {
+        ops::setattr(&mut self.game, ino, size)
     }
-    GameStatus {
-        kitties_needing_pets: needing_pets_count,
-        kitties_at_peace: at_peace_count,
-        kitties_mad: mad_count,
+
+    fn recorder(&mut self) -> Option<&mut Recorder> {
+        self.recorder.as_mut()
     }
 }
 
-const TTL: Timespec = Timespec { sec: 1, nsec: 0 }; // 1 second
-
-const CREATE_TIME: Timespec = Timespec {
-    sec: 1381237736,
-    nsec: 0,
-}; // 2013-10-08 08:56
+impl Filesystem for HelloFS {
+    fn lookup(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        trace::run(self, req, Op::lookup(parent, name)).send_entry(&TTL, reply)
+    }
 
-pub struct HelloFS {
-    inode_table: HashMap<u64, GameEntity>,
-}
+    fn getattr(&mut self, req: &Request, ino: u64, reply: ReplyAttr) {
+        trace::run(self, req, Op::Getattr { ino: ino }).send_attr(&TTL, reply)
+    }
 
-impl Filesystem for HelloFS {
-    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
-        match self.inode_table.get(&parent) {
-            Some(GameEntity::Directory {
-                inode: _,
-                name: _,
-                parent: _,
-                children,
-            }) => {
-                match children
-                    .iter()
-                    .map(|child_inode| self.inode_table.get(child_inode))
-                    .filter(|some_entity| {
-                        some_entity.is_some() && some_entity.map(|e| e.get_name()) == name.to_str()
-                    })
-                    .collect::<Vec<Option<&GameEntity>>>()
-                    .as_slice()
-                {
-                    [Some(file_or_dir)] => reply.entry(&TTL, &file_or_dir.to_file_attr(), 0),
-                    _ => reply.error(ENOENT),
-                }
-            }
-            _ => reply.error(ENOENT),
-        }
+    fn read(&mut self, req: &Request, ino: u64, fh: u64, offset: i64, size: u32, reply: ReplyData) {
+        let op = Op::Read {
+            ino: ino,
+            fh: fh,
+            offset: offset,
+            size: size,
+        };
+        trace::run(self, req, op).send_data(reply)
     }
 
-    fn getattr(&mut self, _req: &Request, ino: u64, reply: ReplyAttr) {
-        match self.inode_table.get(&ino) {
-            Some(dir_or_file) => reply.attr(&TTL, &dir_or_file.to_file_attr()),
-            None => reply.error(ENOENT),
-        }
+    fn readdir(&mut self, req: &Request, ino: u64, fh: u64, offset: i64, reply: ReplyDirectory) {
+        let op = Op::Readdir {
+            ino: ino,
+            fh: fh,
+            offset: offset,
+        };
+        trace::run(self, req, op).send_entries(reply)
     }
 
-    fn read(
+    fn write(
         &mut self,
-        _req: &Request,
+        req: &Request,
         ino: u64,
-        _fh: u64,
+        fh: u64,
         offset: i64,
-        _size: u32,
-        reply: ReplyData,
+        data: &[u8],
+        flags: u32,
+        reply: ReplyWrite,
     ) {
-        match self.inode_table.get(&ino) {
-            Some(f @ GameEntity::File { .. }) => {
-                if f.get_name() == "LiveJournal.txt" {
-                    let status = game_status(&self.inode_table);
-                    reply.data(&status.to_content().as_bytes()[offset as usize..])
-                } else {
-                    reply.data(&f.get_content().as_bytes()[offset as usize..])
-                }
-            }
-            _ => reply.error(ENOENT),
-        }
+        let op = Op::Write {
+            ino: ino,
+            fh: fh,
+            offset: offset,
+            data: data.to_vec(),
+            flags: flags,
+        };
+        trace::run(self, req, op).send_written(reply)
+    }
+
+    fn open(&mut self, req: &Request, ino: u64, flags: u32, reply: ReplyOpen) {
+        let op = Op::Open {
+            ino: ino,
+            flags: flags,
+        };
+        trace::run(self, req, op).send_opened(reply)
     }
 
-    fn readdir(
+    fn mknod(
         &mut self,
-        _req: &Request,
-        ino: u64,
-        _fh: u64,
-        offset: i64,
-        mut reply: ReplyDirectory,
+        req: &Request,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        rdev: u32,
+        reply: ReplyEntry,
     ) {
-        match self.inode_table.get(&ino) {
-            Some(GameEntity::Directory {
-                inode: _,
-                name: _,
-                parent: _,
-                children,
-            }) => {
-                let mut entries: Vec<(u64, FileType, &str)> = Vec::new();
-                entries.push((11, FileType::Directory, "."));
-                entries.push((12, FileType::Directory, ".."));
-                for child in children.iter() {
-                    match self.inode_table.get(child) {
-                        Some(GameEntity::Directory { inode, name, .. }) => {
-                            entries.push((*inode, FileType::Directory, name.as_str()))
-                        }
-                        Some(GameEntity::File { inode, name, .. }) => {
-                            entries.push((*inode, FileType::RegularFile, name.as_str()))
-                        }
-                        None => {}
-                    }
-                }
-                let to_skip = if offset == 0 { offset } else { offset + 1 } as usize;
-                for (i, entry) in entries.into_iter().enumerate().skip(to_skip) {
-                    reply.add(entry.0, i as i64, entry.1, entry.2);
-                }
-                reply.ok();
-            }
-            _ => reply.error(ENOENT),
-        }
+        trace::run(self, req, Op::mknod(parent, name, mode, rdev)).send_entry(&TTL, reply)
     }
 
-    fn write(
+    fn create(
         &mut self,
-        _req: &Request,
-        _ino: u64,
-        _fh: u64,
-        _offset: i64,
-        _data: &[u8],
-        _flags: u32,
-        reply: ReplyWrite,
+        req: &Request,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        flags: u32,
+        reply: ReplyCreate,
     ) {
-        match self.inode_table.get_mut(&_ino) {
-            Some(GameEntity::File {
-                inode: _,
-                name: _,
-                parent: _,
-                content: _,
-                life: _,
-                ref mut pets_needed,
-            }) => {
-                let string = unsafe { std::str::from_utf8_unchecked(_data) };
-                if string == "pets\n" || string == "pets" {
-                    if *pets_needed < 0 {
-                        reply.error(ENOENT);
-                    } else {
-                        *pets_needed -= 1;
-                        reply.written(string.len() as u32);
-                    }
-                } else {
-                    reply.error(ENOENT);
-                }
-            }
-            _ => {
-                println!("UH OH ERROR :(. inode: {} handle: {}", _ino, _fh);
-                reply.error(ENOENT)
-            }
-        }
+        trace::run(self, req, Op::create(parent, name, mode, flags)).send_created(&TTL, reply)
+    }
+
+    fn unlink(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        trace::run(self, req, Op::unlink(parent, name)).send_empty(reply)
+    }
+
+    // `echo pets > cat` truncates the kitten first
+    fn setattr(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        _mode: Option<u32>,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+        size: Option<u64>,
+        _atime: Option<Timespec>,
+        _mtime: Option<Timespec>,
+        _fh: Option<u64>,
+        _crtime: Option<Timespec>,
+        _chgtime: Option<Timespec>,
+        _bkuptime: Option<Timespec>,
+        _flags: Option<u32>,
+        reply: ReplyAttr,
+    ) {
+        let op = Op::Setattr {
+            ino: ino,
+            size: size,
+        };
+        trace::run(self, req, op).send_attr(&TTL, reply)
     }
 
     fn flush(&mut self, _req: &Request, _ino: u64, _fh: u64, _lock_owner: u64, reply: ReplyEmpty) {
@@ -479,7 +224,7 @@ pub fn filter_newlines(document: &Document) -> String {
             Edge::Open(text) if text.is_text() => {
                 let trimmed = text.text().unwrap().trim_matches('\n').trim();
                 if !trimmed.is_empty() {
-                    buffer.push_str(&format!("{}", trimmed));
+                    buffer.push_str(trimmed);
                 }
             }
             Edge::Open(element) if element.is_element() && !element.is_root() => {
@@ -494,35 +239,25 @@ pub fn filter_newlines(document: &Document) -> String {
     buffer
 }
 
-pub fn document_to_game_dir(document: &Node, inode_counter: &mut u64) -> GameDir {
-    let mut files = Vec::new();
-    let mut sub_dirs = Vec::new();
-    let mut game_dir = GameDir::new(*inode_counter, document.tag_name().name().to_string());
-    *inode_counter += 1;
-
+// elements with text in them are kittens (saying the text), other elements
+// are directories. `<LiveJournal.txt>` puts the journal where it is.
+pub fn document_to_dir_spec(document: &Node) -> DirSpec {
+    let mut dir = DirSpec::new(document.tag_name().name());
     for node in document.children() {
         let first_child = node.first_child();
         if node.is_root() {
             // skip
+        } else if node.tag_name().name() == JOURNAL {
+            dir.journal = true;
+        } else if node.has_children() && first_child.is_some_and(|c| c.is_text()) {
+            let content = first_child.unwrap().text().unwrap().to_string();
+            dir.kittens
+                .push(KittenSpec::new(node.tag_name().name()).text(&content));
         } else {
-            if node.has_children() && first_child.map_or(false, |c| c.is_text()) {
-                let content = first_child.unwrap().text().unwrap().to_string();
-                files.push(GameFile::new(
-                    *inode_counter,
-                    node.tag_name().name().to_string(),
-                    content,
-                ));
-                *inode_counter += 1;
-            } else {
-                let sub_game_dir = document_to_game_dir(&node, inode_counter);
-                sub_dirs.push(sub_game_dir);
-            }
+            dir.dirs.push(document_to_dir_spec(&node));
         }
     }
-    *inode_counter += 1;
-    game_dir.files = files;
-    game_dir.sub_dirs = sub_dirs;
-    game_dir
+    dir
 }
 
 pub fn get_xml_file_contents(file: &str) -> String {
@@ -532,28 +267,135 @@ pub fn get_xml_file_contents(file: &str) -> String {
     contents.clone()
 }
 
+// just the kittens and the journal: no leaderboard, event log, `.events` or
+// `.fuschia`
+pub fn hello_fs(raw_xml: &str) -> Result<HelloFS, String> {
+    let raw_doc = Document::parse(raw_xml).map_err(|e| e.to_string())?;
+    let filtered_xml = filter_newlines(&raw_doc);
+    let doc = Document::parse(&filtered_xml).map_err(|e| e.to_string())?;
+    let level = document_to_dir_spec(&doc.root());
+    level.check()?;
+    Ok(HelloFS {
+        game: level.plain_game(),
+        recorder: None,
+    })
+}
+
+const USAGE: &str = "usage: xml <mountpoint> <file.xml> [--record <trace>]
+       xml --replay <trace> <file.xml>";
+
 fn main() {
-    let file = env::args_os().nth(2).unwrap();
-    let mut raw_xml = get_xml_file_contents(file.to_str().unwrap());
-    let raw_doc = Document::parse(&mut raw_xml).unwrap();
-    let mut filtered_xml = filter_newlines(&raw_doc);
-    let doc = Document::parse(&mut filtered_xml).unwrap();
-    let inode_counter = &mut 1;
-    let game_dir: GameDir = document_to_game_dir(&doc.root(), inode_counter);
+    let (trace_args, rest) = match TraceArgs::parse(env::args_os().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("xml: {}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    // a replay doesn't mount anything
+    let mountpoints = if trace_args.replay.is_some() { 0 } else { 1 };
+    if rest.len() != mountpoints + 1 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+    let header = match trace_args.header(0) {
+        Ok(header) => header,
+        Err(e) => {
+            eprintln!("xml: {}", e);
+            process::exit(1);
+        }
+    };
+    let file = &rest[mountpoints];
+    let contents = get_xml_file_contents(file.to_str().unwrap());
+    // the game starts when the trace says it did
+    let mut fs = match speedrun::stopped_at(header.started_at, || hello_fs(&contents)) {
+        Ok(fs) => fs,
+        Err(e) => {
+            eprintln!("xml: {}: {}", file.to_string_lossy(), e);
+            process::exit(1);
+        }
+    };
+    if let Some(ref trace_file) = trace_args.replay {
+        trace::replay_and_exit(&mut fs, trace_file);
+    }
+    fs.recorder = match trace_args.recorder(&header) {
+        Ok(recorder) => recorder,
+        Err(e) => {
+            eprintln!("xml: {}", e);
+            process::exit(1);
+        }
+    };
 
     env_logger::init();
-    let mountpoint = env::args_os().nth(1).unwrap();
+    let mountpoint = &rest[0];
     let options = ["-o", "rw", "-o", "fsname=hello"]
         .iter()
         .map(|o| o.as_ref())
         .collect::<Vec<&OsStr>>();
-    let inode_table = game_dir.to_entity_hash_map();
-    fuse::mount(
-        HelloFS {
-            inode_table: inode_table,
-        },
-        &mountpoint,
-        &options,
-    )
-    .unwrap();
+    fuse::mount(fs, &mountpoint, &options).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use fuse_game::driver::Driver;
+    use libc::ENOTSUP;
+
+    use {hello_fs, HelloFS};
+
+    const ONE_CAT: &str = "<game><LiveJournal.txt>x</LiveJournal.txt><cat>meow</cat></game>";
+
+    // the game in `xml`, mounted
+    fn mount(xml: &str) -> Driver<HelloFS> {
+        Driver::new(hello_fs(xml).unwrap())
+    }
+
+    #[test]
+    fn elements_are_directories_and_text_is_files() {
+        let mut driver = mount(include_str!("../../starcon.xml"));
+        assert_eq!(driver.list("/").unwrap()[0], "HELLO_STARCON");
+        let mut names = driver.list("HELLO_STARCON").unwrap();
+        names.sort();
+        assert_eq!(names, vec!["i_am_a_key", "xml"]);
+        let kitten = driver.read_path("HELLO_STARCON/xml/is_great").unwrap();
+        assert!(kitten.starts_with("what are people complaining about?\n"));
+        assert!(kitten.contains("Please send me 5 pets"));
+    }
+
+    #[test]
+    fn kittens_follow_the_games_rules() {
+        let mut driver = mount(ONE_CAT);
+        let journal = driver.read_path("game/LiveJournal.txt").unwrap();
+        assert!(journal.contains("* 1 kitties still need pets"));
+        driver.pet("game/cat", 5);
+        let journal = driver.read_path("game/LiveJournal.txt").unwrap();
+        assert!(journal.contains("All the kitties are at peace!!!"));
+        assert_eq!(driver.write_path("game/cat", "treats"), Err(ENOTSUP));
+        driver.pet("game/cat", 1);
+        let journal = driver.read_path("game/LiveJournal.txt").unwrap();
+        assert!(journal.contains("SO MANY KITIES ARE MAD AT U"));
+    }
+
+    #[test]
+    fn only_the_journal_goes_with_the_kittens() {
+        let mut driver = mount(ONE_CAT);
+        let mut names = driver.list("game").unwrap();
+        names.sort();
+        assert_eq!(names, vec!["LiveJournal.txt", "cat"]);
+        assert_eq!(driver.list("/").unwrap(), vec!["game"]);
+    }
+
+    #[test]
+    fn kittens_cant_take_names_that_mean_something_else() {
+        for xml in [
+            "<game><control>x</control></game>",
+            "<game><dog.woof>x</dog.woof></game>",
+            "<game><leaderboard.txt>x</leaderboard.txt></game>",
+            "<game><cat>x</cat><cat>y</cat></game>",
+            "<game><a><LiveJournal.txt/></a><b><LiveJournal.txt/></b></game>",
+        ]
+        .iter()
+        {
+            assert!(hello_fs(xml).is_err(), "{}", xml);
+        }
+    }
 }
@@ -0,0 +1,69 @@
+// Boss kittens, which need a team of helpers within a time window.
+
+use std::cmp;
+
+use players::{Outcome, Who};
+use GameFile;
+
+fn default_window_ms() -> i64 {
+    5000
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HelpersBy {
+    #[default]
+    Pid,
+    Uid,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Boss {
+    pub helpers: u32,
+    #[serde(default = "default_window_ms")]
+    pub window_ms: i64,
+    #[serde(default)]
+    pub by: HelpersBy,
+}
+impl Boss {
+    fn helper(&self, who: Who) -> u32 {
+        match self.by {
+            HelpersBy::Pid => who.pid,
+            HelpersBy::Uid => who.uid,
+        }
+    }
+}
+
+impl GameFile {
+    // lets helpers whose care is older than the window go
+    pub fn forget_helpers(&mut self, now_ms: i64, now: i64) {
+        let boss = match self.boss {
+            Some(boss) if self.life > 0 => boss,
+            _ => return,
+        };
+        let before = self.helpers.len();
+        self.helpers.retain(|&(_, at)| now_ms - at < boss.window_ms);
+        if self.helpers.len() != before {
+            self.life = boss.helpers as i32 - self.helpers.len() as i32;
+            self.changed_at = now;
+        }
+    }
+
+    // care for a boss from `who`, which only counts with enough help
+    pub fn help(&mut self, boss: Boss, who: Who, now_ms: i64, now: i64) -> Outcome {
+        self.forget_helpers(now_ms, now);
+        let helper = boss.helper(who);
+        self.helpers.retain(|&(h, _)| h != helper);
+        self.helpers.push((helper, now_ms));
+        let still_needed = boss.helpers as i32 - self.helpers.len() as i32;
+        self.set_life(cmp::max(still_needed, 0), now)
+    }
+}
+
+// what a needy boss adds to its file
+pub fn progress_content(boss: &Boss, helping: usize) -> String {
+    format!(
+        "\nI'M THE BOSS. It takes {} of you petting me within {}ms of each other!\n\n{}/{} helpers petting\n",
+        boss.helpers, boss.window_ms, helping, boss.helpers
+    )
+}
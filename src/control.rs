@@ -0,0 +1,252 @@
+// The `.fuschia/control` file, for running a game without remounting it.
+
+use libc::{c_int, EINVAL};
+use std::cell::RefCell;
+use std::cmp;
+use std::rc::Rc;
+
+use events::Event;
+use generate;
+use speedrun::{self, Speedrun};
+use story::Story;
+use {file, Game, GameDir, GameFile};
+
+pub const CONTROL_DIR: &str = ".fuschia";
+pub const CONTROL: &str = "control";
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+impl Difficulty {
+    fn parse(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    // how much care a kitten needs at this difficulty, when the level says
+    // `level_life`
+    pub fn life(&self, level_life: i32) -> i32 {
+        match self {
+            Difficulty::Easy => cmp::max((level_life + 1) / 2, 1),
+            Difficulty::Normal => level_life,
+            Difficulty::Hard => level_life * 2,
+        }
+    }
+
+    // on hard, a kitten that gets more than this much care in one write is
+    // being over-petted
+    pub fn max_per_write(&self) -> Option<usize> {
+        match self {
+            Difficulty::Hard => Some(2),
+            Difficulty::Easy | Difficulty::Normal => None,
+        }
+    }
+}
+
+enum Command {
+    Reset,
+    New(u64),
+    Difficulty(Difficulty),
+    Pause,
+    Resume,
+}
+
+fn parse_command(line: &str) -> Option<Command> {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    match words.as_slice() {
+        ["reset"] => Some(Command::Reset),
+        ["new", seed] => seed.parse().ok().map(Command::New),
+        ["difficulty", name] => Difficulty::parse(name).map(Command::Difficulty),
+        ["pause"] => Some(Command::Pause),
+        ["resume"] => Some(Command::Resume),
+        _ => None,
+    }
+}
+
+// the `.fuschia` directory, with its inodes from `last_inode + 1` on
+pub fn control_dir(last_inode: u64) -> GameDir {
+    GameDir {
+        inode: last_inode + 1,
+        name: CONTROL_DIR.to_string(),
+        files: vec![Rc::new(RefCell::new(file(last_inode + 2, CONTROL)))],
+        sub_dirs: Vec::new(),
+        locked_until: None,
+        unlocked_at: None,
+    }
+}
+
+pub fn is_control_dir(dir: &GameDir) -> bool {
+    dir.name == CONTROL_DIR
+}
+
+fn kittens(dir: &Rc<RefCell<GameDir>>, f: &mut dyn FnMut(&mut GameFile)) {
+    let borrowed = dir.borrow();
+    for file in borrowed.files.iter() {
+        let mut file = file.borrow_mut();
+        if file.is_kitten() {
+            f(&mut file);
+        }
+    }
+    for sub_dir in borrowed.sub_dirs.iter() {
+        kittens(sub_dir, f);
+    }
+}
+
+impl Game {
+    // the game clock, in milliseconds. It stands still while the game is
+    // paused.
+    pub fn now_ms(&self) -> i64 {
+        self.paused_at.unwrap_or_else(speedrun::now_ms)
+    }
+
+    pub fn now(&self) -> i64 {
+        self.now_ms() / 1000
+    }
+
+    pub fn control_content(&self) -> String {
+        format!(
+            r#"Write a command here to run the game, like: echo reset > .fuschia/control
+
+  reset                         start over with every kitty as needy as it began
+  new <seed>                    start a new random level
+  difficulty easy|normal|hard   change how much care kitties need (and start over)
+  pause                         stop the clock
+  resume                        start it again
+
+difficulty: {}
+paused: {}
+"#,
+            self.difficulty.name(),
+            if self.paused_at.is_some() {
+                "yes"
+            } else {
+                "no"
+            }
+        )
+    }
+
+    pub fn control(&mut self, data: &[u8]) -> Result<u32, c_int> {
+        let text = ::std::str::from_utf8(data).map_err(|_| EINVAL)?;
+        let lines = text
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>();
+        let commands = lines
+            .iter()
+            .map(|l| parse_command(l))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(EINVAL)?;
+        for (line, command) in lines.into_iter().zip(commands) {
+            self.log(Event::Control {
+                command: line.to_string(),
+            });
+            match command {
+                Command::Reset => self.reset(),
+                Command::New(seed) => self.new_game(seed),
+                Command::Difficulty(difficulty) => {
+                    self.difficulty = difficulty;
+                    self.reset();
+                }
+                Command::Pause => self.pause(),
+                Command::Resume => self.resume(),
+            }
+        }
+        Ok(data.len() as u32)
+    }
+
+    // every kitten back to how needy it was at the start (at the current
+    // difficulty) in the first chapter, and the score back to nothing
+    pub fn reset(&mut self) {
+        // before the chapters close, so their kittens go with them
+        self.send_wanderers_home();
+        self.close_book();
+        let now = self.now();
+        let difficulty = self.difficulty;
+        kittens(&self.gamedir, &mut |kitten| {
+            // however hard the game, a boss needs the same number of helpers
+            kitten.max_life = match kitten.boss {
+                Some(_) => kitten.level_life,
+                None => difficulty.life(kitten.level_life),
+            };
+            kitten.life = kitten.max_life;
+            kitten.changed_at = now;
+            kitten.last_cared_ms = None;
+            kitten.hissing = false;
+            kitten.helpers.clear();
+        });
+        self.pets.clear();
+        self.speedrun = Speedrun::default();
+        self.abandoned = 0;
+        self.wander.forget();
+        self.round_up_dogs();
+        self.restart_lesson();
+        self.lock_rooms();
+        self.turn_pages();
+    }
+
+    // replaces the level with a generated one. Everything but the root gets
+    // a fresh inode, so the kernel can't confuse new kittens with old ones.
+    pub fn new_game(&mut self, seed: u64) {
+        let gamedir = generate::generate(seed, &self.generator).build_from(self.last_inode + 1);
+        let control_dir = self
+            .gamedir
+            .borrow()
+            .sub_dirs
+            .iter()
+            .find(|d| is_control_dir(&d.borrow()))
+            .cloned();
+        gamedir.borrow_mut().sub_dirs.extend(control_dir);
+        self.gamedir = gamedir;
+        // generated levels don't tell stories or teach anything
+        self.story = Story::default();
+        self.lesson = None;
+        self.reindex();
+        self.reset();
+    }
+
+    fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(speedrun::now_ms());
+        }
+    }
+
+    // moves every clock forward by however long the game was paused, as if
+    // that time never happened
+    fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            let paused_ms = speedrun::now_ms() - paused_at;
+            kittens(&self.gamedir, &mut |kitten| {
+                kitten.changed_at += paused_ms / 1000;
+                kitten.last_cared_ms = kitten.last_cared_ms.map(|ms| ms + paused_ms);
+                for helper in kitten.helpers.iter_mut() {
+                    helper.1 += paused_ms;
+                }
+            });
+            if self.speedrun.ended_at.is_none() {
+                if let Some(ref mut started_at) = self.speedrun.started_at {
+                    *started_at += paused_ms;
+                }
+            }
+            self.wander.timer.postpone(paused_ms / 1000);
+            self.dogs.timer.postpone(paused_ms / 1000);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// Dogs, which turn up every so often and have to be chased off.
+
+use std::cell::RefCell;
+use std::cmp;
+use std::rc::Rc;
+
+use control::is_control_dir;
+use events::Event;
+use rng::Rng;
+use timer::{Every, Timer};
+use {file, Game, GameDir};
+
+pub const DOG: &str = "dog.woof";
+
+pub type Dogs = Every;
+
+pub struct Pack {
+    pub timer: Timer,
+    rng: Rng,
+}
+impl Pack {
+    pub fn new(settings: Dogs, seed: u64) -> Pack {
+        Pack {
+            timer: Timer::new(settings),
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl GameDir {
+    pub fn has_dog(&self) -> bool {
+        self.files.iter().any(|f| f.borrow().is_dog())
+    }
+}
+
+// every directory under (and including) `dir` a dog could get into
+fn kennels(dir: &Rc<RefCell<GameDir>>, found: &mut Vec<Rc<RefCell<GameDir>>>) {
+    let borrowed = dir.borrow();
+    if is_control_dir(&borrowed) || borrowed.is_locked() {
+        return;
+    }
+    if !borrowed.has_dog() {
+        found.push(Rc::clone(dir));
+    }
+    for sub_dir in borrowed.sub_dirs.iter() {
+        kennels(sub_dir, found);
+    }
+}
+
+// every kitten sharing a directory with a dog needs another pet
+fn bark(dir: &Rc<RefCell<GameDir>>, now: i64) {
+    let borrowed = dir.borrow();
+    if borrowed.has_dog() {
+        for f in borrowed.files.iter() {
+            let mut kitten = f.borrow_mut();
+            if kitten.is_kitten() && kitten.boss.is_none() && kitten.life >= 0 {
+                let life = cmp::min(kitten.life + 1, kitten.max_life);
+                if life != kitten.life {
+                    kitten.life = life;
+                    kitten.changed_at = now;
+                }
+            }
+        }
+    }
+    for sub_dir in borrowed.sub_dirs.iter() {
+        bark(sub_dir, now);
+    }
+}
+
+// takes every dog out of `dir` and everything under it
+fn round_up(dir: &Rc<RefCell<GameDir>>) {
+    let mut borrowed = dir.borrow_mut();
+    borrowed.files.retain(|f| !f.borrow().is_dog());
+    for sub_dir in borrowed.sub_dirs.iter() {
+        round_up(sub_dir);
+    }
+}
+
+impl Game {
+    // called from `tick`, on every operation that looks around or pets
+    // something. Returns whether the dogs came.
+    pub fn tick_dogs(&mut self, now: i64) -> bool {
+        let running = self.speedrun.ended_at.is_none();
+        let due = running && self.paused_at.is_none() && self.dogs.timer.due(now);
+        if due {
+            self.dogs_arrive();
+        }
+        due
+    }
+
+    // the dogs already here bark, then a new one turns up somewhere it
+    // isn't already (if there's anywhere left)
+    pub fn dogs_arrive(&mut self) {
+        bark(&self.gamedir, self.now());
+        let mut candidates = Vec::new();
+        kennels(&self.gamedir, &mut candidates);
+        if candidates.is_empty() {
+            return;
+        }
+        let dir = Rc::clone(self.dogs.rng.pick(&candidates));
+        let inode = self.next_inode();
+        dir.borrow_mut()
+            .files
+            .push(Rc::new(RefCell::new(file(inode, DOG))));
+        self.reindex();
+        let dir = dir.borrow().name.clone();
+        self.log(Event::DogArrived { dir: dir });
+    }
+
+    // `rm dog.woof`
+    pub fn chase_off(&mut self, dir: &Rc<RefCell<GameDir>>) {
+        dir.borrow_mut().files.retain(|f| !f.borrow().is_dog());
+        self.dogs_chased += 1;
+        self.reindex();
+        let dir = dir.borrow().name.clone();
+        self.log(Event::DogChased { dir: dir });
+    }
+
+    // for a reset, which starts over without any dogs
+    pub fn round_up_dogs(&mut self) {
+        round_up(&self.gamedir);
+        self.dogs_chased = 0;
+        self.reindex();
+    }
+}
+
+pub fn dog_content() -> String {
+    r#"WOOF! WOOF! WOOF!
+
+      / \__
+     (    @\___
+     /         O
+    /   (_____/
+   /_____/   U
+
+The kitties in here are scared of me. Chase me off with rm!
+"#
+    .to_string()
+}
+
+// what the diary says about the dogs still around
+pub fn dogs_content(dogs: u32) -> String {
+    match dogs {
+        0 => String::new(),
+        1 => "* 1 dog is scaring the kitties! :O\n".to_string(),
+        n => format!("* {} dogs are scaring the kitties! :O\n", n),
+    }
+}
@@ -0,0 +1,178 @@
+// Driving a file-system without mounting it, for tests.
+
+use libc::{c_int, EIO};
+use serde_json;
+use std::ffi::OsStr;
+
+use level;
+use players::Who;
+use trace::{Attr, Caller, Ops, Reply};
+use Game;
+
+const ROOT: u64 = 1;
+
+// what `cat` asks for at a time
+const CHUNK: u32 = 4096;
+
+pub struct Driver<F: Ops> {
+    pub fs: F,
+    // who every request comes from
+    pub caller: Caller,
+}
+impl<F: Ops> Driver<F> {
+    pub fn new(fs: F) -> Driver<F> {
+        Driver {
+            fs: fs,
+            caller: Caller {
+                uid: 1000,
+                gid: 100,
+                pid: 1,
+            },
+        }
+    }
+
+    pub fn caller(mut self, caller: Caller) -> Self {
+        self.caller = caller;
+        self
+    }
+
+    // what's at `path` ("xxx/5.txt", with or without the leading `/`)
+    pub fn lookup_path(&mut self, path: &str) -> Result<Attr, c_int> {
+        let mut attr = match self.fs.getattr(&self.caller, ROOT) {
+            Reply::Attr(attr) => attr,
+            other => return Err(errno(other)),
+        };
+        for name in path.split('/').filter(|n| !n.is_empty()) {
+            attr = match self.fs.lookup(&self.caller, attr.ino, OsStr::new(name)) {
+                Reply::Entry(attr) => attr,
+                other => return Err(errno(other)),
+            };
+        }
+        Ok(attr)
+    }
+
+    // `cat path`
+    pub fn read_path(&mut self, path: &str) -> Result<String, c_int> {
+        let ino = self.lookup_path(path)?.ino;
+        let mut content = Vec::new();
+        loop {
+            match self
+                .fs
+                .read(&self.caller, ino, 0, content.len() as i64, CHUNK)
+            {
+                Reply::Data(ref data) if data.is_empty() => break,
+                Reply::Data(data) => content.extend(data),
+                other => return Err(errno(other)),
+            }
+        }
+        String::from_utf8(content).map_err(|_| EIO)
+    }
+
+    // `echo -n data > path`
+    pub fn write_path(&mut self, path: &str, data: &str) -> Result<u32, c_int> {
+        let ino = self.lookup_path(path)?.ino;
+        match self.fs.write(&self.caller, ino, 0, 0, data.as_bytes(), 0) {
+            Reply::Written(size) => Ok(size),
+            other => Err(errno(other)),
+        }
+    }
+
+    // `echo pets > path`, `times` times over
+    pub fn pet(&mut self, path: &str, times: usize) {
+        for _ in 0..times {
+            self.write_path(path, "pets\n").unwrap();
+        }
+    }
+
+    // the same requests from now on come from process `pid`
+    pub fn as_pid(&mut self, pid: u32) {
+        self.caller.pid = pid;
+    }
+
+    // `ls -a path`, without `.` and `..`
+    pub fn list(&mut self, path: &str) -> Result<Vec<String>, c_int> {
+        let ino = self.lookup_path(path)?.ino;
+        match self.fs.readdir(&self.caller, ino, 0, 0) {
+            Reply::Entries(entries) => Ok(entries
+                .into_iter()
+                .map(|e| e.name)
+                .filter(|name| name != "." && name != "..")
+                .collect()),
+            other => Err(errno(other)),
+        }
+    }
+}
+
+// the errno in `reply`, which should have been an error
+fn errno(reply: Reply) -> c_int {
+    match reply {
+        Reply::Error(errno) => errno,
+        _ => EIO,
+    }
+}
+
+// the level in `json`, ready to play
+pub fn play(json: &str) -> Game {
+    level::parse(json).unwrap().game()
+}
+
+// the default level
+pub fn starcon() -> Game {
+    play(level::DEFAULT_LEVEL)
+}
+
+// a room with one kitten, `cat`, that's `annoyed` by care within a minute
+pub fn impatient(annoyed: &str) -> Game {
+    play(&format!(
+        r#"{{
+            "name": "impatient",
+            "patience": {{ "cooldown_ms": 60000, "annoyed": "{}" }},
+            "kittens": [{{ "name": "cat" }}]
+        }}"#,
+        annoyed
+    ))
+}
+
+// a room with one kitten, `cat`, that runs `script`
+pub fn scripted(script: &str) -> Game {
+    play(&format!(
+        r#"{{ "name": "scripted", "kittens": [{{ "name": "cat", "script": {} }}] }}"#,
+        serde_json::to_string(script).unwrap()
+    ))
+}
+
+// a room with one boss, `boss.txt`, that needs 3 helpers (counted `by` pid
+// or uid) within a minute
+pub fn boss(by: &str) -> Game {
+    play(&format!(
+        r#"{{
+            "name": "boss",
+            "kittens": [{{ "name": "boss.txt", "boss": {{ "helpers": 3, "window_ms": 60000, "by": "{}" }} }}]
+        }}"#,
+        by
+    ))
+}
+
+// a lesson in `goal` with a kitten at the top, `a.txt`, and one in `room`,
+// `b.txt`, that each want one pet
+pub fn lesson(goal: &str) -> Game {
+    play(&format!(
+        r#"{{
+            "name": "tutorial",
+            "lesson": "{}",
+            "kittens": [{{ "name": "a.txt", "life": 1 }}],
+            "dirs": [{{ "name": "room", "kittens": [{{ "name": "b.txt", "life": 1 }}] }}]
+        }}"#,
+        goal
+    ))
+}
+
+// pets the kitten at `path` as `ME`, `times` times over
+pub fn pet(game: &mut Game, path: &str, times: usize) {
+    for _ in 0..times {
+        game.pet(path, ME).unwrap();
+    }
+}
+
+// who `pet` pets as: the same user and process a `Driver` starts out as
+pub const ME: Who = Who { uid: 1000, pid: 1 };
@@ -0,0 +1,123 @@
+// A history of everything that happened in a game.
+
+use time::{self, Timespec};
+
+use players::Outcome;
+use speedrun;
+use Game;
+
+pub const EVENTS_LOG: &str = "events.log";
+// the live stream next to the journal, see `spectate` in the fuschia binary
+pub const EVENT_STREAM: &str = ".events";
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    Petted {
+        kitten: String,
+        uid: u32,
+        verb: String,
+    },
+    AtPeace {
+        kitten: String,
+    },
+    MadeMad {
+        kitten: String,
+    },
+    Unlocked {
+        dir: String,
+    },
+    Adopted {
+        kitten: String,
+    },
+    Released {
+        kitten: String,
+    },
+    Control {
+        command: String,
+    },
+    Hissed {
+        kitten: String,
+        uid: u32,
+    },
+    DogArrived {
+        dir: String,
+    },
+    DogChased {
+        dir: String,
+    },
+    ChapterBegan {
+        chapter: usize,
+        dir: String,
+    },
+}
+impl Event {
+    fn describe(&self) -> String {
+        match self {
+            Event::Petted { kitten, uid, verb } => {
+                format!("{} got {} from uid {}", kitten, verb, uid)
+            }
+            Event::AtPeace { kitten } => format!("{} is at peace", kitten),
+            Event::MadeMad { kitten } => format!("{} got mad", kitten),
+            Event::Unlocked { dir } => format!("{} unlocked", dir),
+            Event::Adopted { kitten } => format!("{} was adopted", kitten),
+            Event::Released { kitten } => format!("{} was released", kitten),
+            Event::Control { command } => format!("control: {}", command),
+            Event::Hissed { kitten, uid } => format!("{} hissed at uid {}", kitten, uid),
+            Event::DogArrived { dir } => format!("a dog got into {}", dir),
+            Event::DogChased { dir } => format!("a dog was chased out of {}", dir),
+            Event::ChapterBegan { chapter, dir } => {
+                format!("chapter {} began in {}", chapter, dir)
+            }
+        }
+    }
+
+    // what a pet that turned out like `outcome` did to `kitten`, besides
+    // being a pet
+    pub fn from_outcome(outcome: Outcome, kitten: &str) -> Option<Event> {
+        let kitten = kitten.to_string();
+        match outcome {
+            Outcome::AtPeace => Some(Event::AtPeace { kitten: kitten }),
+            Outcome::MadeMad => Some(Event::MadeMad { kitten: kitten }),
+            Outcome::StillNeedy | Outcome::AlreadyMad => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LoggedEvent {
+    // seconds since the epoch
+    pub at: i64,
+    pub event: Event,
+}
+impl LoggedEvent {
+    pub fn line(&self) -> String {
+        let at = time::at_utc(Timespec::new(self.at, 0));
+        format!("{} {}\n", at.rfc3339(), self.event.describe())
+    }
+}
+
+impl Game {
+    // events happen on the wall clock, paused or not
+    pub fn log(&mut self, event: Event) {
+        self.events.push(LoggedEvent {
+            at: speedrun::now_ms() / 1000,
+            event: event,
+        });
+    }
+
+    pub fn events(&self) -> &[LoggedEvent] {
+        &self.events
+    }
+}
+
+pub fn log_content(events: &[LoggedEvent]) -> String {
+    events.iter().map(LoggedEvent::line).collect()
+}
+
+pub fn history_content(events: &[LoggedEvent]) -> String {
+    if events.is_empty() {
+        return String::new();
+    }
+    format!("\nWhat happened:\n\n{}", log_content(events))
+}
@@ -0,0 +1,165 @@
+// Random levels from a seed.
+
+use std::cmp;
+
+use level::{DirSpec, KittenSpec};
+use rng::Rng;
+use species::Species;
+
+const DIR_LETTERS: &[u8] = b"asdfghjklqwertzxcv";
+
+const SPECIES: &[Species] = &[
+    Species::Kitten,
+    Species::Kitten,
+    Species::Tabby,
+    Species::Persian,
+    Species::Siamese,
+];
+
+// mixed into the seed for the species and hidden kitten streams
+const SPECIES_STREAM: u64 = 0x5ca1_ab1e_c0ff_ee00;
+const HIDDEN_STREAM: u64 = 0x0dd_ba11_f00d_cafe;
+
+const KITTEN_NAMES: &[&str] = &[
+    "biscuit", "felix", "garfield", "ginger", "luna", "mittens", "mochi", "nala", "oreo",
+    "pumpkin", "salem", "shadow", "simba", "smokey", "socks", "tigger", "tom", "whiskers",
+];
+
+pub struct Settings {
+    pub depth: u32,
+    pub fanout: u32,
+    pub kittens: u32,
+    pub min_pets: i32,
+    pub max_pets: i32,
+    // kittens are called `<name>.<extension>`
+    pub extension: String,
+    // how many kittens are dotfiles
+    pub hidden: u32,
+}
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            depth: 3,
+            fanout: 2,
+            kittens: 6,
+            min_pets: 3,
+            max_pets: 7,
+            extension: "kitty".to_string(),
+            hidden: 0,
+        }
+    }
+}
+
+// keyboard mash, the finest in directory naming
+fn dir_name(rng: &mut Rng, taken: &[String]) -> String {
+    loop {
+        let len = rng.range(3, 7);
+        let name = (0..len)
+            .map(|_| *rng.pick(DIR_LETTERS) as char)
+            .collect::<String>();
+        if !taken.contains(&name) {
+            return name;
+        }
+    }
+}
+
+fn kitten_name(rng: &mut Rng, taken: &[String], extension: &str) -> String {
+    let base = rng.pick(KITTEN_NAMES);
+    let mut name = format!("{}.{}", base, extension);
+    let mut n = 2;
+    while taken.contains(&name) {
+        name = format!("{}{}.{}", base, n, extension);
+        n += 1;
+    }
+    name
+}
+
+fn generate_dir(rng: &mut Rng, name: String, depth: u32, settings: &Settings) -> DirSpec {
+    let mut dir = DirSpec::new(&name);
+    if depth > 0 {
+        let mut names = Vec::new();
+        for _ in 0..rng.range(1, u64::from(settings.fanout)) {
+            let name = dir_name(rng, &names);
+            names.push(name.clone());
+            dir.dirs.push(generate_dir(rng, name, depth - 1, settings));
+        }
+    }
+    dir
+}
+
+fn count_dirs(dir: &DirSpec) -> usize {
+    1 + dir.dirs.iter().map(count_dirs).sum::<usize>()
+}
+
+// walks the tree in the same (pre-)order as `count_dirs` numbers it,
+// dropping `litters[i]` kittens into the i-th directory.
+fn place_kittens(
+    rng: &mut Rng,
+    dir: &mut DirSpec,
+    litters: &[u32],
+    index: &mut usize,
+    settings: &Settings,
+) {
+    let mut taken = dir.dirs.iter().map(|d| d.name.clone()).collect::<Vec<_>>();
+    for _ in 0..litters[*index] {
+        let name = kitten_name(rng, &taken, &settings.extension);
+        taken.push(name.clone());
+        let life = rng.range(settings.min_pets as u64, settings.max_pets as u64) as i32;
+        dir.kittens.push(KittenSpec {
+            life: life,
+            ..KittenSpec::new(&name)
+        });
+    }
+    *index += 1;
+    for sub_dir in dir.dirs.iter_mut() {
+        place_kittens(rng, sub_dir, litters, index, settings);
+    }
+}
+
+fn pick_species(rng: &mut Rng, dir: &mut DirSpec) {
+    for kitten in dir.kittens.iter_mut() {
+        kitten.species = *rng.pick(SPECIES);
+    }
+    for sub_dir in dir.dirs.iter_mut() {
+        pick_species(rng, sub_dir);
+    }
+}
+
+fn kittens_mut(dir: &mut DirSpec) -> Vec<&mut KittenSpec> {
+    let mut kittens = dir.kittens.iter_mut().collect::<Vec<_>>();
+    for sub_dir in dir.dirs.iter_mut() {
+        kittens.extend(kittens_mut(sub_dir));
+    }
+    kittens
+}
+
+// turns `count` of the kittens into dotfiles. Names within a directory are
+// already unique, so they stay unique with a dot in front.
+fn hide_kittens(rng: &mut Rng, root: &mut DirSpec, count: u32) {
+    let mut kittens = kittens_mut(root);
+    for _ in 0..cmp::min(count as usize, kittens.len()) {
+        let i = rng.range(0, kittens.len() as u64 - 1) as usize;
+        let kitten = kittens.swap_remove(i);
+        kitten.name = format!(".{}", kitten.name);
+    }
+}
+
+pub fn generate(seed: u64, settings: &Settings) -> DirSpec {
+    let mut rng = Rng::new(seed);
+    let mut root = generate_dir(&mut rng, format!("seed-{}", seed), settings.depth, settings);
+    root.journal = true;
+
+    let mut litters = vec![0; count_dirs(&root)];
+    for _ in 0..settings.kittens {
+        let i = rng.range(0, litters.len() as u64 - 1) as usize;
+        litters[i] += 1;
+    }
+    place_kittens(&mut rng, &mut root, &litters, &mut 0, settings);
+    pick_species(&mut Rng::new(seed ^ SPECIES_STREAM), &mut root);
+    hide_kittens(
+        &mut Rng::new(seed ^ HIDDEN_STREAM),
+        &mut root,
+        settings.hidden,
+    );
+    root
+}
@@ -0,0 +1,137 @@
+// Lessons, for onboarding sessions that teach one shell tool at a time.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use players::{Pet, Who};
+use Game;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Goal {
+    FindExec,
+    Xargs,
+}
+impl Goal {
+    fn describe(&self) -> &'static str {
+        match self {
+            Goal::FindExec => "pet every kitty with a single find -exec",
+            Goal::Xargs => "pet every kitty from one process, with the names piped to xargs",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Lesson {
+    pub goal: Goal,
+    // every room listed so far, and which process listed it (once each)
+    listed: Vec<(u32, u64)>,
+    // every room something was looked up in, and which process did it
+    // (once each)
+    looked_up: Vec<(u32, u64)>,
+}
+impl Lesson {
+    pub fn new(goal: Goal) -> Lesson {
+        Lesson {
+            goal: goal,
+            listed: Vec::new(),
+            looked_up: Vec::new(),
+        }
+    }
+
+    // the rooms each process looked at one way or another, out of `seen`
+    fn rooms_by_pid<'a, I: Iterator<Item = &'a (u32, u64)>>(
+        seen: I,
+    ) -> BTreeMap<u32, BTreeSet<u64>> {
+        let mut rooms: BTreeMap<u32, BTreeSet<u64>> = BTreeMap::new();
+        for &(pid, dir) in seen {
+            rooms.entry(pid).or_default().insert(dir);
+        }
+        rooms
+    }
+
+    // whether the goal was met by listing (some of) `rooms` and petting
+    // (all of) `kittens` the way `pets` say, and why not if it wasn't
+    pub fn judge(&self, rooms: &[u64], kittens: &[u64], pets: &[Pet]) -> Result<(), String> {
+        let listed = Lesson::rooms_by_pid(self.listed.iter());
+        let looked_in = Lesson::rooms_by_pid(self.listed.iter().chain(self.looked_up.iter()));
+        let mut petted: BTreeMap<u32, BTreeSet<u64>> = BTreeMap::new();
+        for pet in pets.iter() {
+            petted.entry(pet.pid).or_default().insert(pet.inode);
+        }
+        // every process that opens a kitten looks it up, so only listing a
+        // room counts as going looking for kittens
+        let looked_around = |pid: &u32| listed.get(pid).is_some_and(|r| !r.is_empty());
+        match self.goal {
+            Goal::FindExec => {
+                let walker = looked_in
+                    .iter()
+                    .find(|(_, r)| rooms.iter().all(|room| r.contains(room)))
+                    .map(|(pid, _)| *pid)
+                    .ok_or_else(|| "no one process looked in every room".to_string())?;
+                if petted.contains_key(&walker) {
+                    return Err(format!(
+                        "pid {} looked everywhere but did some petting itself",
+                        walker
+                    ));
+                }
+                if let Some(pid) = petted.keys().find(|pid| looked_around(pid)) {
+                    return Err(format!("pid {} went looking for kitties on its own", pid));
+                }
+                Ok(())
+            }
+            Goal::Xargs => {
+                let (pid, _) = petted
+                    .iter()
+                    .find(|(_, k)| kittens.iter().all(|kitten| k.contains(kitten)))
+                    .ok_or_else(|| "no one process petted every kitty".to_string())?;
+                if looked_around(pid) {
+                    return Err(format!(
+                        "pid {} went looking for kitties instead of being told",
+                        pid
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Game {
+    // `who` listed the room `dir`
+    pub fn watch(&mut self, dir: u64, who: Who) {
+        if let Some(ref mut lesson) = self.lesson {
+            if !lesson.listed.contains(&(who.pid, dir)) {
+                lesson.listed.push((who.pid, dir));
+            }
+        }
+    }
+
+    // `who` looked something up in the room `dir`
+    pub fn watch_lookup(&mut self, dir: u64, who: Who) {
+        if let Some(ref mut lesson) = self.lesson {
+            if !lesson.looked_up.contains(&(who.pid, dir)) {
+                lesson.looked_up.push((who.pid, dir));
+            }
+        }
+    }
+
+    // for a reset
+    pub fn restart_lesson(&mut self) {
+        if let Some(ref mut lesson) = self.lesson {
+            lesson.listed.clear();
+            lesson.looked_up.clear();
+        }
+    }
+}
+
+// what the journal says about the lesson: the goal, then (once every
+// kitten is at peace) how it went
+pub fn lesson_content(goal: Goal, result: Option<&Result<(), String>>) -> String {
+    let mut content = format!("\nLesson: {}\n", goal.describe());
+    match result {
+        Some(Ok(())) => content.push_str("\nLESSON PASSED!! You're a natural :3\n"),
+        Some(Err(e)) => content.push_str(&format!("\nNot quite: {}. Try again with reset!\n", e)),
+        None => {}
+    }
+    content
+}
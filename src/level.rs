@@ -0,0 +1,328 @@
+// Level definitions for fuschia.
+
+use serde_json;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::rc::Rc;
+
+use boss::Boss;
+use control::{CONTROL, CONTROL_DIR};
+use dogs::DOG;
+use events::{EVENTS_LOG, EVENT_STREAM};
+use lesson::{Goal, Lesson};
+use locks::Unlock;
+use patience::Patience;
+use species::Species;
+use story::{Chapter, Story};
+use {file, Game, GameDir, JOURNAL, LEADERBOARD};
+
+pub const DEFAULT_LEVEL: &str = include_str!("../levels/starcon.json");
+
+fn default_life() -> i32 {
+    5
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct KittenSpec {
+    pub name: String,
+    #[serde(default = "default_life")]
+    pub life: i32,
+    #[serde(default)]
+    pub species: Species,
+    #[serde(default)]
+    pub script: Option<String>,
+    #[serde(default)]
+    pub patience: Option<Patience>,
+    #[serde(default)]
+    pub boss: Option<Boss>,
+    #[serde(default)]
+    pub text: Option<String>,
+}
+impl KittenSpec {
+    // a plain kitten that wants the usual number of pets
+    pub fn new(name: &str) -> KittenSpec {
+        KittenSpec {
+            name: name.to_string(),
+            life: default_life(),
+            species: Species::default(),
+            script: None,
+            patience: None,
+            boss: None,
+            text: None,
+        }
+    }
+
+    pub fn text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DirSpec {
+    pub name: String,
+    #[serde(default)]
+    pub journal: bool,
+    #[serde(default)]
+    pub kittens: Vec<KittenSpec>,
+    #[serde(default)]
+    pub dirs: Vec<DirSpec>,
+    #[serde(default)]
+    pub locked_until: Option<Unlock>,
+    // for the kittens in here (and further down) that don't have their own
+    #[serde(default)]
+    pub patience: Option<Patience>,
+    // only the top directory tells a story
+    #[serde(default)]
+    pub story: Option<String>,
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+    // only the top directory can be a lesson too
+    #[serde(default)]
+    pub lesson: Option<Goal>,
+}
+impl DirSpec {
+    // an empty directory
+    pub fn new(name: &str) -> DirSpec {
+        DirSpec {
+            name: name.to_string(),
+            journal: false,
+            kittens: Vec::new(),
+            dirs: Vec::new(),
+            locked_until: None,
+            patience: None,
+            story: None,
+            chapters: Vec::new(),
+            lesson: None,
+        }
+    }
+
+    fn journal_count(&self) -> usize {
+        let here = if self.journal { 1 } else { 0 };
+        here + self.dirs.iter().map(|d| d.journal_count()).sum::<usize>()
+    }
+
+    // everything `parse` checks, for levels that come from somewhere else
+    pub fn check(&self) -> Result<(), String> {
+        if self.journal_count() > 1 {
+            return Err("only one directory can have the journal".to_string());
+        }
+        if self.locked_until.is_some() {
+            return Err("the top directory can't be locked".to_string());
+        }
+        self.validate(&self.name)?;
+        for (i, chapter) in self.chapters.iter().enumerate() {
+            let path = format!("chapter {}", i + 2);
+            if chapter.dir.journal_count() > 0 {
+                return Err(format!("{}: chapters can't have the journal", path));
+            }
+            let dir = &chapter.dir;
+            if dir.story.is_some() || !dir.chapters.is_empty() || dir.lesson.is_some() {
+                return Err(format!(
+                    "{}: a chapter can't have a story, chapters or a lesson of its own",
+                    path
+                ));
+            }
+            if chapter.dir.name == CONTROL_DIR {
+                return Err(format!(
+                    "{}: '{}' is taken by the control file",
+                    path, CONTROL_DIR
+                ));
+            }
+            chapter
+                .dir
+                .validate(&format!("{}/{}", path, chapter.dir.name))?;
+        }
+        Ok(())
+    }
+
+    fn validate(&self, path: &str) -> Result<(), String> {
+        let mut names = HashSet::new();
+        if self.journal {
+            names.insert(JOURNAL);
+            names.insert(LEADERBOARD);
+            names.insert(EVENTS_LOG);
+            names.insert(EVENT_STREAM);
+        }
+        let kitten_names = self.kittens.iter().map(|k| k.name.as_str());
+        let dir_names = self.dirs.iter().map(|d| d.name.as_str());
+        for name in kitten_names.chain(dir_names) {
+            if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+                return Err(format!("{}: '{}' is not a valid file name", path, name));
+            }
+            if !names.insert(name) {
+                return Err(format!("{}: '{}' is in there twice", path, name));
+            }
+        }
+        let reserved = |k: &&KittenSpec| {
+            [
+                JOURNAL,
+                LEADERBOARD,
+                EVENTS_LOG,
+                EVENT_STREAM,
+                CONTROL,
+                CONTROL_DIR,
+                DOG,
+            ]
+            .contains(&k.name.as_str())
+        };
+        if let Some(kitten) = self.kittens.iter().find(reserved) {
+            return Err(format!("{}: '{}' can't be a kitten", path, kitten.name));
+        }
+        if let Some(kitten) = self
+            .kittens
+            .iter()
+            .find(|k| k.boss.map(|b| b.helpers) == Some(0))
+        {
+            return Err(format!(
+                "{}: boss '{}' needs at least 1 helper",
+                path, kitten.name
+            ));
+        }
+        if let Some(dir) = self
+            .dirs
+            .iter()
+            .find(|d| d.story.is_some() || !d.chapters.is_empty())
+        {
+            return Err(format!(
+                "{}/{}: only the top directory can tell a story",
+                path, dir.name
+            ));
+        }
+        if let Some(dir) = self.dirs.iter().find(|d| d.lesson.is_some()) {
+            return Err(format!(
+                "{}/{}: only the top directory can be a lesson",
+                path, dir.name
+            ));
+        }
+        if let Some(dir) = self.dirs.iter().find(|d| d.name == CONTROL_DIR) {
+            return Err(format!(
+                "{}: '{}' is taken by the control file",
+                path, dir.name
+            ));
+        }
+        for dir in self.dirs.iter() {
+            dir.validate(&format!("{}/{}", path, dir.name))?;
+        }
+        Ok(())
+    }
+
+    // inodes go depth-first: the directory, the files next to the journal,
+    // the kittens, then the sub-directories
+    fn to_game_dir(
+        &self,
+        journal_here: bool,
+        extras: bool,
+        patience: Option<Patience>,
+        inode_counter: &mut u64,
+    ) -> GameDir {
+        let patience = self.patience.or(patience);
+        let mut game_dir = GameDir {
+            inode: *inode_counter,
+            name: self.name.clone(),
+            files: Vec::new(),
+            sub_dirs: Vec::new(),
+            locked_until: self.locked_until,
+            unlocked_at: None,
+        };
+        *inode_counter += 1;
+
+        if journal_here {
+            let with_the_journal = if extras {
+                &[JOURNAL, LEADERBOARD, EVENTS_LOG, EVENT_STREAM][..]
+            } else {
+                &[JOURNAL][..]
+            };
+            for name in with_the_journal.iter() {
+                game_dir
+                    .files
+                    .push(Rc::new(RefCell::new(file(*inode_counter, name))));
+                *inode_counter += 1;
+            }
+        }
+        for kitten in self.kittens.iter() {
+            // a boss needs one pet from each helper
+            let life = kitten.boss.map_or(kitten.life, |b| b.helpers as i32);
+            let game_file = file(*inode_counter, &kitten.name)
+                .life(life)
+                .species(kitten.species)
+                .script(kitten.script.clone())
+                .patience(kitten.patience.or(patience))
+                .boss(kitten.boss)
+                .content(kitten.text.as_ref().map_or("", String::as_str));
+            game_dir.files.push(Rc::new(RefCell::new(game_file)));
+            *inode_counter += 1;
+        }
+        for dir in self.dirs.iter() {
+            let sub_dir = dir.to_game_dir(dir.journal, extras, patience, inode_counter);
+            game_dir.sub_dirs.push(Rc::new(RefCell::new(sub_dir)));
+        }
+        game_dir
+    }
+
+    // builds the game tree, allocating inodes from 1 (the root).
+    pub fn build(&self) -> Rc<RefCell<GameDir>> {
+        self.build_from(2)
+    }
+
+    // builds the game tree with the root at inode 1 (FUSE insists) and
+    // everything else from `first_inode` on.
+    pub fn build_from(&self, first_inode: u64) -> Rc<RefCell<GameDir>> {
+        self.build_with(first_inode, true)
+    }
+
+    // `extras` puts the leaderboard and the event log and stream next to the
+    // journal
+    fn build_with(&self, first_inode: u64, extras: bool) -> Rc<RefCell<GameDir>> {
+        let journal_in_root = self.journal || self.journal_count() == 0;
+        let mut inode_counter = first_inode - 1;
+        let mut root = self.to_game_dir(journal_in_root, extras, None, &mut inode_counter);
+        root.inode = 1;
+        Rc::new(RefCell::new(root))
+    }
+
+    // builds a directory for a game that's already going, with inodes from
+    // `first_inode` on
+    pub fn build_sub_dir(&self, first_inode: u64) -> Rc<RefCell<GameDir>> {
+        let mut inode_counter = first_inode;
+        Rc::new(RefCell::new(self.to_game_dir(
+            false,
+            false,
+            None,
+            &mut inode_counter,
+        )))
+    }
+
+    // a game of this level, with its story (if it has one) ready to go
+    pub fn game(&self) -> Game {
+        let mut game = Game::new(self.build());
+        game.story = Story::new(self.story.clone(), self.chapters.clone());
+        game.lesson = self.lesson.map(Lesson::new);
+        game.turn_pages();
+        game
+    }
+
+    // a game of just the kittens and the journal, without the leaderboard,
+    // the events or `.fuschia`, for file-systems that don't serve those
+    pub fn plain_game(&self) -> Game {
+        Game::plain(self.build_with(2, false))
+    }
+}
+
+pub fn parse(json: &str) -> Result<DirSpec, String> {
+    let level: DirSpec =
+        serde_json::from_str(json).map_err(|e| format!("bad level definition: {}", e))?;
+    level.check()?;
+    Ok(level)
+}
+
+pub fn load(path: &Path) -> Result<DirSpec, String> {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+    parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
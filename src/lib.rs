@@ -0,0 +1,1145 @@
+// Code shared by the fuschia, starcon and xml file-systems, game and all.
+
+extern crate fuse;
+extern crate libc;
+extern crate rhai;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate time;
+
+mod adopt;
+pub mod boss;
+pub mod control;
+pub mod dogs;
+#[cfg(any(test, feature = "testing"))]
+pub mod driver;
+pub mod events;
+pub mod generate;
+pub mod lesson;
+pub mod level;
+pub mod locks;
+pub mod needs;
+pub mod ops;
+pub mod patience;
+pub mod players;
+mod rng;
+pub mod save;
+pub mod script;
+pub mod species;
+pub mod speedrun;
+pub mod story;
+#[cfg(test)]
+mod tests;
+pub mod timer;
+pub mod trace;
+pub mod wander;
+pub mod xattrs;
+
+use libc::{c_int, EACCES, EAGAIN, EINVAL, EISDIR, ENOENT, ENOTDIR, ENOTSUP};
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use boss::Boss;
+use control::Difficulty;
+use dogs::{Dogs, Pack, DOG};
+use events::{Event, LoggedEvent, EVENTS_LOG, EVENT_STREAM};
+use generate::Settings;
+use lesson::{Goal, Lesson};
+use locks::Unlock;
+use needs::Needs;
+use patience::{Annoyed, Patience};
+use players::{Outcome, Pet, PlayerStats, Who};
+use script::Scripts;
+use species::Species;
+use speedrun::Speedrun;
+use story::Story;
+use wander::{Hint, Wander, Wandering};
+
+pub const JOURNAL: &str = "LiveJournal.txt";
+pub const LEADERBOARD: &str = "leaderboard.txt";
+
+#[derive(Debug, Eq, Hash, PartialEq)]
+pub struct GameFile {
+    name: String,
+    inode: u64,
+    // what the level says the kitten says, before anything else
+    content: String,
+    life: i32,
+    // how many pets the kitten needed to begin with
+    max_life: i32,
+    // how many pets the level says it needs, before difficulty
+    level_life: i32,
+    // when `life` last changed, in seconds since the epoch
+    changed_at: i64,
+    species: Species,
+    // Rhai source for a kitten that does its own thing, see `script`
+    script: Option<String>,
+    // what went wrong the last time the script ran, if it did
+    script_error: Option<String>,
+    patience: Option<Patience>,
+    // when care last counted, in milliseconds since the epoch
+    last_cared_ms: Option<i64>,
+    // whether the last care was too fast for it
+    hissing: bool,
+    boss: Option<Boss>,
+    // who's been helping a boss lately, and when (in milliseconds)
+    helpers: Vec<(u32, i64)>,
+    // the directory (by inode) a kitten that wandered off started out in
+    home: Option<u64>,
+}
+impl GameFile {
+    pub fn new(inode: u64, name: String, content: String) -> GameFile {
+        GameFile {
+            name: name,
+            inode: inode,
+            content: content,
+            life: 5,
+            max_life: 5,
+            level_life: 5,
+            changed_at: needs::now(),
+            species: Species::Kitten,
+            script: None,
+            script_error: None,
+            patience: None,
+            last_cared_ms: None,
+            hissing: false,
+            boss: None,
+            helpers: Vec::new(),
+            home: None,
+        }
+    }
+
+    pub fn content(mut self, content: &str) -> Self {
+        self.content = content.to_string();
+        self
+    }
+
+    pub fn life(mut self, life: i32) -> Self {
+        self.life = life;
+        self.max_life = life;
+        self.level_life = life;
+        self
+    }
+
+    pub fn max_life(mut self, max_life: i32) -> Self {
+        self.max_life = max_life;
+        self
+    }
+
+    pub fn level_life(mut self, level_life: i32) -> Self {
+        self.level_life = level_life;
+        self
+    }
+
+    pub fn changed_at(mut self, changed_at: i64) -> Self {
+        self.changed_at = changed_at;
+        self
+    }
+
+    pub fn species(mut self, species: Species) -> Self {
+        self.species = species;
+        self
+    }
+
+    pub fn script(mut self, script: Option<String>) -> Self {
+        self.script = script;
+        self
+    }
+
+    pub fn patience(mut self, patience: Option<Patience>) -> Self {
+        self.patience = patience;
+        self
+    }
+
+    pub fn last_cared_ms(mut self, last_cared_ms: Option<i64>) -> Self {
+        self.last_cared_ms = last_cared_ms;
+        self
+    }
+
+    pub fn hissing(mut self, hissing: bool) -> Self {
+        self.hissing = hissing;
+        self
+    }
+
+    pub fn boss(mut self, boss: Option<Boss>) -> Self {
+        self.boss = boss;
+        self
+    }
+
+    pub fn helpers(mut self, helpers: Vec<(u32, i64)>) -> Self {
+        self.helpers = helpers;
+        self
+    }
+
+    pub fn home(mut self, home: Option<u64>) -> Self {
+        self.home = home;
+        self
+    }
+
+    pub fn get_content(&self) -> String {
+        let mut content = if self.content.is_empty() {
+            String::new()
+        } else {
+            format!("{}\n", self.content)
+        };
+        content.push_str(&match Mood::of(self.life) {
+            Mood::Needy => self
+                .species
+                .needy_content(&self.name, self.life, self.wants()),
+            Mood::AtPeace => self.species.happy_content(&self.name),
+            Mood::Mad => self.species.mad_content(&self.name),
+        });
+        if let Some(ref boss) = self.boss {
+            if self.life > 0 {
+                content.push_str(&boss::progress_content(boss, self.helpers.len()));
+            }
+        }
+        if self.hissing {
+            if let Some(ref patience) = self.patience {
+                content.push_str(&patience::hiss_content(patience));
+            }
+        }
+        match self.script_error {
+            Some(ref e) => format!("{}{}", content, script::error_content(e)),
+            None => content,
+        }
+    }
+
+    pub fn wants(&self) -> &'static str {
+        self.species.wants(self.max_life - self.life)
+    }
+
+    // whether the kitten would take every one of `verbs`, in order. Mad
+    // kittens take anything their species likes, it won't help.
+    pub fn accepts(&self, verbs: &[&str]) -> bool {
+        let mut life = self.life;
+        for verb in verbs.iter() {
+            if life < 0 {
+                if !self.species.verbs().contains(verb) {
+                    return false;
+                }
+            } else if *verb != self.species.wants(self.max_life - life) {
+                return false;
+            } else {
+                life -= 1;
+            }
+        }
+        true
+    }
+
+    pub fn is_journal(&self) -> bool {
+        self.name == JOURNAL
+    }
+
+    pub fn is_leaderboard(&self) -> bool {
+        self.name == LEADERBOARD
+    }
+
+    pub fn is_events_log(&self) -> bool {
+        self.name == EVENTS_LOG
+    }
+
+    pub fn is_event_stream(&self) -> bool {
+        self.name == EVENT_STREAM
+    }
+
+    pub fn is_control(&self) -> bool {
+        self.name == control::CONTROL
+    }
+
+    pub fn is_dog(&self) -> bool {
+        self.name == DOG
+    }
+
+    pub fn is_kitten(&self) -> bool {
+        !self.is_journal()
+            && !self.is_leaderboard()
+            && !self.is_events_log()
+            && !self.is_event_stream()
+            && !self.is_control()
+            && !self.is_dog()
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.is_kitten() || self.is_control()
+    }
+
+    // dotfiles only show up with `ls -a`
+    pub fn is_hidden(&self) -> bool {
+        self.name.starts_with('.')
+    }
+
+    // a kitten at peace gets mad when it's cared for again, and a mad
+    // kitten stays mad no matter how much more care it gets.
+    pub fn pet(&mut self, now: i64) -> Outcome {
+        if self.life < 0 {
+            return Outcome::AlreadyMad;
+        }
+        self.life -= 1;
+        self.changed_at = now;
+        if self.life > 0 {
+            Outcome::StillNeedy
+        } else if self.life == 0 {
+            Outcome::AtPeace
+        } else {
+            Outcome::MadeMad
+        }
+    }
+
+    // a script decided how many pets the kitten needs now
+    pub fn set_life(&mut self, life: i32, now: i64) -> Outcome {
+        let was = self.life;
+        if life != was {
+            self.life = life;
+            self.changed_at = now;
+        }
+        match Mood::of(life) {
+            Mood::Needy => Outcome::StillNeedy,
+            Mood::AtPeace => Outcome::AtPeace,
+            Mood::Mad if was < 0 => Outcome::AlreadyMad,
+            Mood::Mad => Outcome::MadeMad,
+        }
+    }
+
+    // over-petting on hard skips straight to mad
+    pub fn anger(&mut self, now: i64) -> Outcome {
+        if self.life < 0 {
+            return Outcome::AlreadyMad;
+        }
+        self.life = -1;
+        self.changed_at = now;
+        Outcome::MadeMad
+    }
+}
+
+// the kernel doesn't know how big our (generated) files are, so clamp the
+// requested window to whatever content we have.
+pub fn read_slice(content: &[u8], offset: i64, size: u32) -> &[u8] {
+    let start = cmp::min(cmp::max(offset, 0) as usize, content.len());
+    let end = cmp::min(start + size as usize, content.len());
+    &content[start..end]
+}
+
+pub fn file(inode: u64, name: &str) -> GameFile {
+    GameFile::new(inode, name.to_string(), "".to_string())
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mood {
+    Needy,
+    AtPeace,
+    Mad,
+}
+impl Mood {
+    // a kitten with `life` pets still to go
+    pub fn of(life: i32) -> Mood {
+        if life > 0 {
+            Mood::Needy
+        } else if life == 0 {
+            Mood::AtPeace
+        } else {
+            Mood::Mad
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mood::Needy => "needy",
+            Mood::AtPeace => "peace",
+            Mood::Mad => "mad",
+        }
+    }
+}
+
+// how many kittens are in each mood
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Tally {
+    pub needing_pets: u32,
+    pub at_peace: u32,
+    pub mad: u32,
+}
+impl Tally {
+    pub fn add(&mut self, life: i32) {
+        match Mood::of(life) {
+            Mood::Needy => self.needing_pets += 1,
+            Mood::AtPeace => self.at_peace += 1,
+            Mood::Mad => self.mad += 1,
+        }
+    }
+
+    // the top of the diary, the same in every game
+    pub fn diary(&self) -> String {
+        format!(
+            r#"Dear Diary,
+
+All my friends are at StarCon! :(
+
+I have to stay at home and pet these kitties :~(
+
+Here's what I've done so far:
+
+* {} kitties still need pets
+* {} kitties are at peace with the world
+* {} kitties are mad because I petted them too much!
+"#,
+            self.needing_pets, self.at_peace, self.mad
+        )
+    }
+}
+
+pub struct GameStatus {
+    pub tally: Tally,
+    hidden_kitties_needing_pets: u32,
+    wanderings: Vec<(String, Hint)>,
+    locked_rooms: Vec<String>,
+    players: Vec<PlayerStats>,
+    elapsed_ms: i64,
+    writes: u32,
+    wasted_pets: u32,
+    abandoned: u32,
+    dogs: u32,
+    dogs_chased: u32,
+    story: Vec<(usize, String)>,
+    lesson: Option<Goal>,
+    // how the lesson went, once every kitten is at peace
+    lesson_result: Option<Result<(), String>>,
+}
+impl GameStatus {
+    // there's no winning with dogs still around
+    pub fn is_game_over(&self) -> bool {
+        self.tally.needing_pets == 0 && self.dogs == 0
+    }
+
+    pub fn score(&self) -> i64 {
+        speedrun::score(
+            self.tally.at_peace,
+            self.wasted_pets,
+            self.writes,
+            self.abandoned,
+            self.elapsed_ms,
+        )
+    }
+
+    pub fn to_content(&self) -> String {
+        let mut content = self.diary_content();
+        content.push_str(&story::story_content(&self.story));
+        if let Some(goal) = self.lesson {
+            content.push_str(&lesson::lesson_content(goal, self.lesson_result.as_ref()));
+        }
+        if self.is_game_over() {
+            content.push_str(&format!(
+                "\nFinal time: {}\nWrites: {}\nWasted pets: {}\nAbandoned kitties: {}\nDogs chased off: {}\nSCORE: {}\n",
+                speedrun::format_elapsed(self.elapsed_ms),
+                self.writes,
+                self.wasted_pets,
+                self.abandoned,
+                self.dogs_chased,
+                self.score()
+            ));
+        }
+        content.push_str(&players::breakdown(&self.players));
+        content
+    }
+
+    fn diary_content(&self) -> String {
+        if self.is_game_over() && self.tally.mad == 0 {
+            r#"GAME OVER!!
+
+All the kitties are at peace!!!
+
+             *     ,MMM8&&&.            *
+                  MMMM88&&&&&    .
+                 MMMM88&&&&&&&
+     *           MMM88&&&&&&&&
+                 MMM88&&&&&&&&
+                 'MMM88&&&&&&'
+                   'MMM8&&&'      *
+          |\___/|
+          )     (             .              '
+         =\     /=
+           )===(       *
+          /     \
+          |     |
+         /       \
+         \       /
+  _/\_/\_/\__  _/_/\_/\_/\_/\_/\_/\_/\_/\_/\_
+  |  |  |  |( (  |  |  |  |  |  |  |  |  |  |
+  |  |  |  | ) ) |  |  |  |  |  |  |  |  |  |
+  |  |  |  |(_(  |  |  |  |  |  |  |  |  |  |
+  |  |  |  |  |  |  |  |  |  |  |  |  |  |  |
+  |  |  |  |  |  |  |  |  |  |  |  |  |  |  |
+  "#
+            .to_string()
+        } else if self.is_game_over() {
+            format!(
+                r#"GAME OVER!!!
+
+SO MANY KITIES ARE MAD AT U!!!!!!!!!!!! :-(
+       ___
+   _.-|   |          |\__/,|   (`\
+  (   | {} |          |o o  |__ _) )
+   "-.|___|        _.( T   )  `  /
+    .--'-`-.     _((_ `^--' /_<  \
+  .+|______|__.-||__)`-'(((/  (((/
+
+        "#,
+                self.tally.mad
+            )
+        } else {
+            format!(
+                "{}* {} spent petting so far\n* {} writes to kitties\n* {} pets wasted on kitties that didn't need them\n{}{}{}{}{}",
+                self.tally.diary(),
+                speedrun::format_elapsed(self.elapsed_ms),
+                self.writes,
+                self.wasted_pets,
+                self.abandoned_content(),
+                dogs::dogs_content(self.dogs),
+                self.hidden_content(),
+                wander::hints_content(&self.wanderings),
+                locks::locked_content(&self.locked_rooms)
+            )
+        }
+    }
+
+    fn abandoned_content(&self) -> String {
+        match self.abandoned {
+            0 => String::new(),
+            1 => "* 1 kitty was abandoned before it was at peace :'(\n".to_string(),
+            n => format!(
+                "* {} kitties were abandoned before they were at peace :'(\n",
+                n
+            ),
+        }
+    }
+
+    // says how many kitties are hiding, but not where
+    fn hidden_content(&self) -> String {
+        match self.hidden_kitties_needing_pets {
+            0 => String::new(),
+            1 => "\nI can hear 1 kitty meowing but I can't see it anywhere...\n".to_string(),
+            n => format!(
+                "\nI can hear {} kitties meowing but I can't see them anywhere...\n",
+                n
+            ),
+        }
+    }
+}
+
+pub struct Game {
+    gamedir: Rc<RefCell<GameDir>>,
+    pub save_file: Option<PathBuf>,
+    inode_table: HashMap<u64, Either>,
+    parent_table: HashMap<u64, Vec<Either>>,
+    pets: Vec<Pet>,
+    pub needs: Needs,
+    speedrun: Speedrun,
+    pub wander: Wander,
+    pub dogs: Pack,
+    // dogs removed (unlinked) this game
+    dogs_chased: u32,
+    // kittens released (unlinked) before they were at peace
+    abandoned: u32,
+    // the biggest inode handed out so far
+    last_inode: u64,
+    difficulty: Difficulty,
+    // when the game was paused, in milliseconds since the epoch
+    paused_at: Option<i64>,
+    // for `new <seed>` in the control file
+    pub generator: Settings,
+    events: Vec<LoggedEvent>,
+    scripts: Scripts,
+    story: Story,
+    lesson: Option<Lesson>,
+}
+impl Game {
+    pub fn new(gamedir: Rc<RefCell<GameDir>>) -> Game {
+        let mut fs = Game::plain(gamedir);
+        // every game gets a `.fuschia/control`, including ones resumed from
+        // before there was such a thing
+        let has_control = fs
+            .gamedir
+            .borrow()
+            .sub_dirs
+            .iter()
+            .any(|d| control::is_control_dir(&d.borrow()));
+        if !has_control {
+            let control_dir = control::control_dir(fs.last_inode);
+            fs.gamedir
+                .borrow_mut()
+                .sub_dirs
+                .push(Rc::new(RefCell::new(control_dir)));
+            fs.reindex();
+        }
+        fs
+    }
+
+    // a game of just what's in `gamedir`, without a `.fuschia/control`
+    pub fn plain(gamedir: Rc<RefCell<GameDir>>) -> Game {
+        let mut fs = Game {
+            gamedir: gamedir,
+            save_file: None,
+            inode_table: HashMap::new(),
+            parent_table: HashMap::new(),
+            pets: Vec::new(),
+            needs: Needs::default(),
+            speedrun: Speedrun::default(),
+            wander: Wander::new(Wandering::default(), 0),
+            dogs: Pack::new(Dogs::default(), 0),
+            dogs_chased: 0,
+            abandoned: 0,
+            last_inode: 1,
+            difficulty: Difficulty::default(),
+            paused_at: None,
+            generator: Settings::default(),
+            events: Vec::new(),
+            scripts: Scripts::default(),
+            story: Story::default(),
+            lesson: None,
+        };
+        fs.reindex();
+        fs.unlock_rooms();
+        fs
+    }
+
+    fn is_locked(&self, ino: u64) -> bool {
+        match self.inode_table.get(&ino) {
+            Some(Either::Directory { dir }) => dir.borrow().is_locked(),
+            _ => false,
+        }
+    }
+
+    // rebuilds the inode and parent tables after the tree changed shape
+    pub fn reindex(&mut self) {
+        let mut inode_table = HashMap::new();
+        update_inode_map(&self.gamedir, &mut inode_table);
+        self.last_inode = cmp::max(
+            self.last_inode,
+            inode_table.keys().cloned().max().unwrap_or(1),
+        );
+        self.inode_table = inode_table;
+        self.parent_table.clear();
+        update_parent_map(&self.gamedir, &mut self.parent_table);
+    }
+
+    // adds `dir` (and everything in it) to `parent` while the game is going,
+    // keeping the inode and parent tables up to date without rebuilding them
+    pub fn graft(&mut self, parent: &Rc<RefCell<GameDir>>, dir: Rc<RefCell<GameDir>>) {
+        let parent_inode = parent.borrow().inode;
+        parent.borrow_mut().sub_dirs.push(Rc::clone(&dir));
+        self.parent_table
+            .entry(parent_inode)
+            .or_default()
+            .push(Either::Directory {
+                dir: Rc::clone(&dir),
+            });
+        update_inode_map(&dir, &mut self.inode_table);
+        update_parent_map(&dir, &mut self.parent_table);
+        self.last_inode = cmp::max(
+            self.last_inode,
+            self.inode_table.keys().cloned().max().unwrap_or(1),
+        );
+    }
+
+    // brings `either` up to date with the time that passed since anyone
+    // last looked at it. The journal and leaderboard talk about every
+    // kitten, so looking at them catches up the whole game.
+    pub fn catch_up(&self, either: &Either) {
+        if let Either::File { file: f } = either {
+            if f.borrow().is_kitten() {
+                self.catch_up_kitten(f);
+            } else {
+                self.catch_up_all();
+            }
+        }
+    }
+
+    // every kitten, before anything judges how they're all doing
+    pub fn catch_up_all(&self) {
+        for kitten in self.inode_table.values().filter_map(Either::kitten) {
+            self.catch_up_kitten(kitten);
+        }
+    }
+
+    fn catch_up_kitten(&self, kitten: &Rc<RefCell<GameFile>>) {
+        let now = self.now();
+        let mut kitten = kitten.borrow_mut();
+        kitten.catch_up(now, &self.needs);
+        kitten.forget_helpers(self.now_ms(), now);
+    }
+
+    pub fn status(&self) -> GameStatus {
+        let mut tally = Tally::default();
+        let mut hidden_needing_pets_count: u32 = 0;
+        let mut kittens = Vec::new();
+        let dogs = self
+            .inode_table
+            .values()
+            .filter_map(Either::file)
+            .filter(|f| f.borrow().is_dog())
+            .count() as u32;
+
+        for kitten in self.inode_table.values().filter_map(Either::kitten) {
+            let borrowed_file = kitten.borrow();
+            tally.add(borrowed_file.life);
+            kittens.push(borrowed_file.inode);
+            if borrowed_file.life > 0 && borrowed_file.is_hidden() {
+                hidden_needing_pets_count += 1;
+            }
+        }
+        let lesson_result = match self.lesson {
+            Some(ref lesson) if tally.needing_pets == 0 && tally.mad == 0 => {
+                let rooms = self
+                    .inode_table
+                    .values()
+                    .filter_map(|e| match e {
+                        Either::Directory { dir } => Some(dir.borrow()),
+                        Either::File { .. } => None,
+                    })
+                    .filter(|d| !control::is_control_dir(d) && !d.is_locked())
+                    .map(|d| d.inode)
+                    .collect::<Vec<_>>();
+                Some(lesson.judge(&rooms, &kittens, &self.pets))
+            }
+            _ => None,
+        };
+        GameStatus {
+            tally: tally,
+            hidden_kitties_needing_pets: hidden_needing_pets_count,
+            wanderings: self.wanderings(),
+            locked_rooms: self.locked_rooms(),
+            players: players::player_stats(&self.pets),
+            elapsed_ms: self.speedrun.elapsed(self.now_ms()),
+            writes: self.speedrun.writes,
+            wasted_pets: speedrun::wasted_pets(&self.pets),
+            abandoned: self.abandoned,
+            dogs: dogs,
+            dogs_chased: self.dogs_chased,
+            story: self.story.pages(),
+            lesson: self.lesson.as_ref().map(|l| l.goal),
+            lesson_result: lesson_result,
+        }
+    }
+
+    pub fn file_content(&self, file: &GameFile) -> String {
+        if file.is_journal() {
+            format!(
+                "{}{}",
+                self.status().to_content(),
+                events::history_content(&self.events)
+            )
+        } else if file.is_leaderboard() {
+            players::leaderboard_content(&players::player_stats(&self.pets))
+        } else if file.is_events_log() {
+            events::log_content(&self.events)
+        } else if file.is_event_stream() {
+            // there's only ever something to read from an open handle
+            String::new()
+        } else if file.is_control() {
+            self.control_content()
+        } else if file.is_dog() {
+            dogs::dog_content()
+        } else {
+            self.scripts.content(file, self.now())
+        }
+    }
+
+    // handles a write of `data` to the kitten `ino` from `who`, returning
+    // how much of it was used
+    fn care(&mut self, ino: u64, data: &[u8], who: Who) -> Result<u32, c_int> {
+        let f = match self.inode_table.get(&ino) {
+            Some(Either::File { file: f }) => Rc::clone(f),
+            Some(Either::Directory { .. }) => return Err(EISDIR),
+            None => return Err(ENOENT),
+        };
+        let mut borrowed_file = f.borrow_mut();
+        if !borrowed_file.is_kitten() {
+            return Err(EACCES);
+        }
+        let verbs = species::parse_verbs(data).ok_or(EINVAL)?;
+        let now = self.now();
+        borrowed_file.catch_up(now, &self.needs);
+        if !borrowed_file.accepts(&verbs) {
+            return Err(ENOTSUP);
+        }
+        // the game clock, so a pause doesn't count towards a cooldown
+        let now_ms = self.now_ms();
+        let last_cared_ms = borrowed_file.last_cared_ms;
+        let patience = borrowed_file.patience;
+        if let Some(patience) = patience {
+            let rushed = (0..verbs.len()).any(|i| patience.rushed(last_cared_ms, now_ms, i));
+            if rushed && patience.annoyed == Annoyed::Hiss {
+                borrowed_file.hissing = true;
+                self.log(Event::Hissed {
+                    kitten: borrowed_file.name.clone(),
+                    uid: who.uid,
+                });
+                return Err(EAGAIN);
+            }
+        }
+        let limit = self.difficulty.max_per_write().unwrap_or(verbs.len());
+        for (i, verb) in verbs.iter().enumerate() {
+            let times = match patience {
+                Some(p) if p.annoyed == Annoyed::Double && p.rushed(last_cared_ms, now_ms, i) => 2,
+                _ => 1,
+            };
+            for _ in 0..times {
+                let boss = borrowed_file.boss.filter(|_| borrowed_file.life > 0);
+                let outcome = if i >= limit {
+                    borrowed_file.anger(now)
+                } else if let Some(boss) = boss {
+                    borrowed_file.help(boss, who, now_ms, now)
+                } else {
+                    self.scripts.pet(&mut borrowed_file, verb, now)
+                };
+                self.pets.push(Pet {
+                    uid: who.uid,
+                    pid: who.pid,
+                    inode: ino,
+                    outcome: outcome,
+                    at: needs::now(),
+                });
+                self.log(Event::Petted {
+                    kitten: borrowed_file.name.clone(),
+                    uid: who.uid,
+                    verb: verb.to_string(),
+                });
+                if let Some(event) = Event::from_outcome(outcome, &borrowed_file.name) {
+                    self.log(event);
+                }
+            }
+        }
+        if !verbs.is_empty() {
+            borrowed_file.last_cared_ms = Some(now_ms);
+            borrowed_file.hissing = false;
+        }
+        Ok(data.len() as u32)
+    }
+
+    pub fn save(&self) {
+        if let Some(ref path) = self.save_file {
+            if let Err(e) = save::store(self, path) {
+                eprintln!("fuschia: {}", e);
+            }
+        }
+    }
+
+    fn stat(&self, either: &Either) -> Stat {
+        match either {
+            Either::Directory { dir: d } => {
+                let borrowed_dir = d.borrow();
+                Stat {
+                    ino: borrowed_dir.inode,
+                    size: 0,
+                    is_dir: true,
+                    locked: borrowed_dir.is_locked(),
+                    mtime: borrowed_dir.unlocked_at,
+                }
+            }
+            Either::File { file: f } => {
+                let borrowed_file = f.borrow();
+                Stat {
+                    ino: borrowed_file.inode,
+                    size: self.file_content(&borrowed_file).len() as u64,
+                    is_dir: false,
+                    locked: false,
+                    mtime: Some(borrowed_file.changed_at),
+                }
+            }
+        }
+    }
+}
+
+// what a file or directory looks like from outside
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Stat {
+    pub ino: u64,
+    pub size: u64,
+    pub is_dir: bool,
+    // nobody gets into a locked room
+    pub locked: bool,
+    // when it last changed, in seconds since the epoch, if it ever has
+    pub mtime: Option<i64>,
+}
+
+// one thing in a directory
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry {
+    pub ino: u64,
+    pub is_dir: bool,
+    pub name: String,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Opened {
+    File,
+    // `.events`, which whoever opened it has to stream
+    EventStream,
+}
+
+// the game the way a file-system sees it
+impl Game {
+    // looks `name` up in `parent` for `who`
+    pub fn lookup(&mut self, parent: u64, name: &str, who: Who) -> Result<Stat, c_int> {
+        self.tick();
+        self.speedrun.start(self.now_ms());
+        if self.is_locked(parent) {
+            return Err(EACCES);
+        }
+        self.watch_lookup(parent, who);
+        let children = self.parent_table.get(&parent).ok_or(ENOENT)?;
+        let filtered = children
+            .iter()
+            .filter(|c| c.name() == name)
+            .collect::<Vec<&Either>>();
+        if filtered.len() == 1 {
+            let child = filtered[0];
+            self.catch_up(child);
+            Ok(self.stat(child))
+        } else {
+            Err(ENOENT)
+        }
+    }
+
+    // only looks: it doesn't tick, so the size it gives is the size of
+    // what the next read returns
+    pub fn getattr(&mut self, ino: u64) -> Result<Stat, c_int> {
+        let either = self.inode_table.get(&ino).ok_or(ENOENT)?;
+        self.catch_up(either);
+        Ok(self.stat(either))
+    }
+
+    // reads what the file says before the tick, which is what getattr (or
+    // the lookup before it) sized it as
+    pub fn read(&mut self, ino: u64, offset: i64, size: u32) -> Result<Vec<u8>, c_int> {
+        let data = match self.inode_table.get(&ino) {
+            Some(either @ Either::File { .. }) => {
+                self.catch_up(either);
+                let content = self.file_content(&either.file().unwrap().borrow());
+                Ok(read_slice(content.as_bytes(), offset, size).to_vec())
+            }
+            _ => Err(ENOENT),
+        };
+        self.tick();
+        data
+    }
+
+    // what's in the directory `ino`, sub-directories first, for `who`
+    pub fn list(&mut self, ino: u64, who: Who) -> Result<Vec<Entry>, c_int> {
+        self.tick();
+        self.speedrun.start(self.now_ms());
+        if self.is_locked(ino) {
+            return Err(EACCES);
+        }
+        self.watch(ino, who);
+        match self.inode_table.get(&ino) {
+            Some(Either::Directory { dir: d }) => {
+                let borrowed_directory = d.borrow();
+                let mut entries = Vec::new();
+                for subdir in borrowed_directory.sub_dirs.iter() {
+                    let borrowed = subdir.borrow();
+                    entries.push(Entry {
+                        ino: borrowed.inode,
+                        is_dir: true,
+                        name: borrowed.name.clone(),
+                    });
+                }
+                for file in borrowed_directory.files.iter() {
+                    let borrowed = file.borrow();
+                    entries.push(Entry {
+                        ino: borrowed.inode,
+                        is_dir: false,
+                        name: borrowed.name.clone(),
+                    });
+                }
+                Ok(entries)
+            }
+            _ => Err(ENOENT),
+        }
+    }
+
+    // handles a write of `data` to `ino` from `who`, returning how much of
+    // it was used
+    pub fn write(&mut self, ino: u64, data: &[u8], who: Who) -> Result<u32, c_int> {
+        self.tick();
+        let control = self.inode_table.get(&ino).and_then(Either::file);
+        let written = if control.map(|f| f.borrow().is_control()) == Some(true) {
+            self.control(data)
+        } else {
+            self.speedrun.writes += 1;
+            self.care(ino, data, who)
+        };
+        self.catch_up_all();
+        self.unlock_rooms();
+        self.turn_pages();
+        if self.status().is_game_over() {
+            self.speedrun.finish(self.now_ms());
+        }
+        // even a write that fails counts, and a hiss is remembered
+        self.save();
+        written
+    }
+
+    pub fn open(&self, ino: u64, writing: bool) -> Result<Opened, c_int> {
+        match self.inode_table.get(&ino) {
+            Some(Either::File { file: f }) => {
+                let borrowed_file = f.borrow();
+                if writing && !borrowed_file.is_writable() {
+                    Err(EACCES)
+                } else if borrowed_file.is_event_stream() {
+                    Ok(Opened::EventStream)
+                } else {
+                    Ok(Opened::File)
+                }
+            }
+            Some(Either::Directory { .. }) => Err(EISDIR),
+            None => Err(ENOENT),
+        }
+    }
+
+    // `echo pets > 3.txt` truncates the kitten before writing to it. The
+    // content is generated from the kitten's life so there's nothing to
+    // truncate, we just have to say yes.
+    pub fn setattr(&self, ino: u64, size: Option<u64>) -> Result<Stat, c_int> {
+        match self.inode_table.get(&ino) {
+            Some(Either::File { file: f }) if size.is_some() && !f.borrow().is_writable() => {
+                Err(EACCES)
+            }
+            Some(dir_or_file) => Ok(self.stat(dir_or_file)),
+            None => Err(ENOENT),
+        }
+    }
+
+    // the inode at `path` ("xxx/5.txt", with or without the leading `/`),
+    // if someone could walk there
+    pub fn resolve(&self, path: &str) -> Result<u64, c_int> {
+        let mut ino = 1;
+        for name in path.split('/').filter(|n| !n.is_empty()) {
+            if self.is_locked(ino) {
+                return Err(EACCES);
+            }
+            let children = self.parent_table.get(&ino).ok_or(ENOTDIR)?;
+            ino = children
+                .iter()
+                .find(|c| c.name() == name)
+                .map(Either::inode)
+                .ok_or(ENOENT)?;
+        }
+        Ok(ino)
+    }
+
+    // gives the kitten at `path` whatever it wants right now, like
+    // `echo <verb> > path` would
+    pub fn pet(&mut self, path: &str, who: Who) -> Result<Outcome, c_int> {
+        let ino = self.resolve(path)?;
+        let verb = {
+            let either = self.inode_table.get(&ino).ok_or(ENOENT)?;
+            self.catch_up(either);
+            match either.file() {
+                Some(f) if f.borrow().is_kitten() => f.borrow().wants(),
+                Some(_) => return Err(EACCES),
+                None => return Err(EISDIR),
+            }
+        };
+        self.write(ino, verb.as_bytes(), who)?;
+        // a write that went through always leaves a pet behind
+        Ok(self.pets.last().unwrap().outcome)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct GameDir {
+    inode: u64,
+    name: String,
+    files: Vec<Rc<RefCell<GameFile>>>,
+    sub_dirs: Vec<Rc<RefCell<GameDir>>>,
+    locked_until: Option<Unlock>,
+    // when the lock opened, in seconds since the epoch
+    unlocked_at: Option<i64>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Either {
+    Directory { dir: Rc<RefCell<GameDir>> },
+    File { file: Rc<RefCell<GameFile>> },
+}
+impl Either {
+    pub fn name(&self) -> String {
+        match self {
+            Either::File { file: f } => f.borrow().name.clone(),
+            Either::Directory { dir: d } => d.borrow().name.clone(),
+        }
+    }
+
+    pub fn inode(&self) -> u64 {
+        match self {
+            Either::File { file: f } => f.borrow().inode,
+            Either::Directory { dir: d } => d.borrow().inode,
+        }
+    }
+
+    pub fn file(&self) -> Option<&Rc<RefCell<GameFile>>> {
+        match self {
+            Either::File { file: f } => Some(f),
+            Either::Directory { .. } => None,
+        }
+    }
+
+    pub fn kitten(&self) -> Option<&Rc<RefCell<GameFile>>> {
+        self.file().filter(|f| f.borrow().is_kitten())
+    }
+}
+
+pub fn update_inode_map(gamedir: &Rc<RefCell<GameDir>>, hash_map: &mut HashMap<u64, Either>) {
+    // not sure how safe this is? is borrowed_gamedir
+    // borrowed twice?
+    let borrowed_gamedir = (*gamedir).borrow();
+
+    hash_map.insert(
+        borrowed_gamedir.inode,
+        Either::Directory {
+            dir: Rc::clone(&gamedir),
+        },
+    );
+    for file in borrowed_gamedir.files.iter() {
+        let inode: u64 = (*file).borrow().inode;
+        hash_map.insert(
+            inode,
+            Either::File {
+                file: Rc::clone(&file),
+            },
+        );
+    }
+    for subdir in borrowed_gamedir.sub_dirs.iter() {
+        update_inode_map(subdir, hash_map);
+    }
+}
+
+pub fn update_parent_map(gamedir: &Rc<RefCell<GameDir>>, hash_map: &mut HashMap<u64, Vec<Either>>) {
+    let borrowed_gamedir = (*gamedir).borrow();
+    let mut vec =
+        Vec::with_capacity(borrowed_gamedir.files.len() + borrowed_gamedir.sub_dirs.len());
+    vec.extend(
+        borrowed_gamedir
+            .files
+            .iter()
+            .map(|f| Either::File { file: Rc::clone(f) }),
+    );
+    vec.extend(
+        borrowed_gamedir
+            .sub_dirs
+            .iter()
+            .map(|d| Either::Directory { dir: Rc::clone(d) }),
+    );
+
+    hash_map.insert(borrowed_gamedir.inode, vec);
+
+    // recurse
+    for subdir in borrowed_gamedir.sub_dirs.iter() {
+        update_parent_map(subdir, hash_map);
+    }
+}
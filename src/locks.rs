@@ -0,0 +1,123 @@
+// Locked rooms, so a level can be played one part at a time.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use events::Event;
+use {Game, GameDir};
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Unlock {
+    Parent,
+    KittensAtPeace(u32),
+}
+impl Unlock {
+    fn describe(&self, parent: &str) -> String {
+        match self {
+            Unlock::Parent => format!("opens when every kitty in {} is at peace", parent),
+            Unlock::KittensAtPeace(1) => "opens when a kitty is at peace".to_string(),
+            Unlock::KittensAtPeace(n) => format!("opens when {} kitties are at peace", n),
+        }
+    }
+}
+
+impl GameDir {
+    pub fn is_locked(&self) -> bool {
+        self.locked_until.is_some() && self.unlocked_at.is_none()
+    }
+
+    fn kittens_at_peace(&self) -> bool {
+        self.files.iter().all(|f| {
+            let f = f.borrow();
+            !f.is_kitten() || f.life == 0
+        })
+    }
+}
+
+// a directory with a lock, its path, its parent and its parent's path
+type LockedDir = (Rc<RefCell<GameDir>>, String, Rc<RefCell<GameDir>>, String);
+
+// every directory under `dir` with a lock
+fn locked_dirs(dir: &Rc<RefCell<GameDir>>, path: &str, found: &mut Vec<LockedDir>) {
+    for sub_dir in dir.borrow().sub_dirs.iter() {
+        let sub_path = if path.is_empty() {
+            sub_dir.borrow().name.clone()
+        } else {
+            format!("{}/{}", path, sub_dir.borrow().name)
+        };
+        if sub_dir.borrow().locked_until.is_some() {
+            found.push((
+                Rc::clone(sub_dir),
+                sub_path.clone(),
+                Rc::clone(dir),
+                path.to_string(),
+            ));
+        }
+        locked_dirs(sub_dir, &sub_path, found);
+    }
+}
+
+impl Game {
+    // opens any locked room whose condition has been met
+    pub fn unlock_rooms(&mut self) {
+        let at_peace = self.status().tally.at_peace;
+        let now = self.now();
+        let mut found = Vec::new();
+        locked_dirs(&self.gamedir, "", &mut found);
+        for (dir, path, parent, _) in found {
+            let mut dir = dir.borrow_mut();
+            if !dir.is_locked() {
+                continue;
+            }
+            let open = match dir.locked_until {
+                Some(Unlock::Parent) => parent.borrow().kittens_at_peace(),
+                Some(Unlock::KittensAtPeace(n)) => at_peace >= n,
+                None => false,
+            };
+            if open {
+                dir.unlocked_at = Some(now);
+                self.log(Event::Unlocked { dir: path });
+            }
+        }
+    }
+
+    pub fn lock_rooms(&mut self) {
+        let mut found = Vec::new();
+        locked_dirs(&self.gamedir, "", &mut found);
+        for (dir, _, _, _) in found {
+            dir.borrow_mut().unlocked_at = None;
+        }
+        self.unlock_rooms();
+    }
+
+    // the rooms still locked, and what opens them
+    pub fn locked_rooms(&self) -> Vec<String> {
+        let mut found = Vec::new();
+        locked_dirs(&self.gamedir, "", &mut found);
+        found
+            .into_iter()
+            .filter(|(dir, _, _, _)| dir.borrow().is_locked())
+            .map(|(dir, path, _, parent_path)| {
+                let parent_path = if parent_path.is_empty() {
+                    "the top room".to_string()
+                } else {
+                    parent_path
+                };
+                let unlock = dir.borrow().locked_until.unwrap();
+                format!("{} ({})", path, unlock.describe(&parent_path))
+            })
+            .collect()
+    }
+}
+
+pub fn locked_content(rooms: &[String]) -> String {
+    if rooms.is_empty() {
+        return String::new();
+    }
+    let mut content = "\nLocked rooms:\n\n".to_string();
+    for room in rooms.iter() {
+        content.push_str(&format!("* {}\n", room));
+    }
+    content
+}
@@ -0,0 +1,42 @@
+// Kittens whose needs change over time.
+
+use std::cmp;
+
+use speedrun;
+use GameFile;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Needs {
+    pub lonely_after: Option<i64>,
+    pub cooldown: Option<i64>,
+}
+
+pub fn now() -> i64 {
+    speedrun::now_ms() / 1000
+}
+
+impl GameFile {
+    pub fn catch_up(&mut self, now: i64, needs: &Needs) {
+        // a needy boss's life is how many helpers it's missing
+        if self.boss.is_some() && self.life > 0 {
+            return;
+        }
+        if self.life < 0 {
+            match needs.cooldown {
+                Some(cooldown) if now - self.changed_at >= cooldown => {
+                    self.life = 0;
+                    self.changed_at += cooldown;
+                }
+                _ => return,
+            }
+        }
+        if let Some(lonely_after) = needs.lonely_after {
+            let intervals = (now - self.changed_at) / lonely_after;
+            if intervals > 0 {
+                let lonelier = cmp::min(intervals, i64::from(self.max_life)) as i32;
+                self.life = cmp::min(self.life + lonelier, self.max_life);
+                self.changed_at += intervals * lonely_after;
+            }
+        }
+    }
+}
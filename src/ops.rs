@@ -0,0 +1,145 @@
+// Answering `trace::Ops` requests from a `Game`.
+
+use fuse::consts::FOPEN_DIRECT_IO;
+use fuse::{FileAttr, FileType};
+use libc::{EPERM, O_ACCMODE, O_RDONLY, S_IFMT, S_IFREG};
+use std::ffi::OsStr;
+use time::Timespec;
+
+use players::Who;
+use trace::{self, Caller, Reply};
+use {Game, Stat};
+
+const CREATE_TIME: Timespec = Timespec {
+    sec: 1381237736,
+    nsec: 0,
+}; // 2013-10-08 08:56
+
+impl<'a> From<&'a Caller> for Who {
+    fn from(caller: &'a Caller) -> Who {
+        Who {
+            uid: caller.uid,
+            pid: caller.pid,
+        }
+    }
+}
+
+pub fn to_file_attr(stat: Stat) -> FileAttr {
+    let mtime = stat.mtime.map_or(CREATE_TIME, |t| Timespec::new(t, 0));
+    FileAttr {
+        ino: stat.ino,
+        size: stat.size,
+        blocks: 1,
+        atime: CREATE_TIME,
+        mtime: mtime,
+        ctime: CREATE_TIME,
+        crtime: CREATE_TIME,
+        kind: if stat.is_dir {
+            FileType::Directory
+        } else {
+            FileType::RegularFile
+        },
+        perm: if stat.locked { 0o000 } else { 0o644 },
+        nlink: 1,
+        uid: 1000,
+        gid: 100,
+        rdev: 0,
+        flags: 0,
+    }
+}
+
+pub fn lookup(game: &mut Game, caller: &Caller, parent: u64, name: &OsStr) -> Reply {
+    match game.lookup(parent, name.to_str().unwrap_or(""), Who::from(caller)) {
+        Ok(stat) => Reply::entry(to_file_attr(stat)),
+        Err(errno) => Reply::Error(errno),
+    }
+}
+
+pub fn getattr(game: &mut Game, ino: u64) -> Reply {
+    match game.getattr(ino) {
+        Ok(stat) => Reply::attr(to_file_attr(stat)),
+        Err(errno) => Reply::Error(errno),
+    }
+}
+
+pub fn read(game: &mut Game, ino: u64, offset: i64, size: u32) -> Reply {
+    match game.read(ino, offset, size) {
+        Ok(data) => Reply::Data(data),
+        Err(errno) => Reply::Error(errno),
+    }
+}
+
+pub fn readdir(game: &mut Game, caller: &Caller, ino: u64, offset: i64) -> Reply {
+    let children = match game.list(ino, Who::from(caller)) {
+        Ok(children) => children,
+        Err(errno) => return Reply::Error(errno),
+    };
+    let mut entries: Vec<(u64, FileType, String)> = Vec::new();
+    entries.push((1111, FileType::Directory, ".".to_string()));
+    entries.push((11112, FileType::Directory, "..".to_string()));
+    for child in children {
+        let kind = if child.is_dir {
+            FileType::Directory
+        } else {
+            FileType::RegularFile
+        };
+        entries.push((child.ino, kind, child.name));
+    }
+    Reply::Entries(trace::list_from(entries, offset))
+}
+
+pub fn write(game: &mut Game, caller: &Caller, ino: u64, data: &[u8]) -> Reply {
+    match game.write(ino, data, Who::from(caller)) {
+        Ok(written) => Reply::Written(written),
+        Err(errno) => Reply::Error(errno),
+    }
+}
+
+// whether `open` was asked for with `flags` that let it write
+pub fn writing(flags: u32) -> bool {
+    flags & (O_ACCMODE as u32) != O_RDONLY as u32
+}
+
+// content changes with every pet, so the kernel mustn't cache it. `.events`
+// needs a file handle of its own, so fuschia opens that itself.
+pub fn open(game: &mut Game, ino: u64, flags: u32) -> Reply {
+    match game.open(ino, writing(flags)) {
+        Ok(_) => Reply::Opened {
+            fh: 0,
+            flags: FOPEN_DIRECT_IO,
+        },
+        Err(errno) => Reply::Error(errno),
+    }
+}
+
+pub fn create(game: &mut Game, parent: u64, name: &OsStr) -> Reply {
+    match game.create(parent, name.to_str().unwrap_or("")) {
+        Ok(stat) => Reply::created(to_file_attr(stat), 0, FOPEN_DIRECT_IO),
+        Err(errno) => Reply::Error(errno),
+    }
+}
+
+// only regular files can be kittens
+pub fn mknod(game: &mut Game, parent: u64, name: &OsStr, mode: u32) -> Reply {
+    if mode & S_IFMT != S_IFREG {
+        return Reply::Error(EPERM);
+    }
+    match game.create(parent, name.to_str().unwrap_or("")) {
+        Ok(stat) => Reply::entry(to_file_attr(stat)),
+        Err(errno) => Reply::Error(errno),
+    }
+}
+
+pub fn unlink(game: &mut Game, parent: u64, name: &OsStr) -> Reply {
+    match game.unlink(parent, name.to_str().unwrap_or("")) {
+        Ok(()) => Reply::Empty,
+        Err(errno) => Reply::Error(errno),
+    }
+}
+
+pub fn setattr(game: &mut Game, ino: u64, size: Option<u64>) -> Reply {
+    match game.setattr(ino, size) {
+        Ok(stat) => Reply::attr(to_file_attr(stat)),
+        Err(errno) => Reply::Error(errno),
+    }
+}
@@ -0,0 +1,31 @@
+// Kittens that don't like being rushed.
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Annoyed {
+    #[default]
+    Hiss,
+    Double,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Patience {
+    pub cooldown_ms: i64,
+    #[serde(default)]
+    pub annoyed: Annoyed,
+}
+
+impl Patience {
+    // whether the `nth` verb of a write at `now_ms` is too soon after care
+    // that counted at `last_ms`
+    pub fn rushed(&self, last_ms: Option<i64>, now_ms: i64, nth: usize) -> bool {
+        nth > 0 || last_ms.is_some_and(|last| now_ms - last < self.cooldown_ms)
+    }
+}
+
+pub fn hiss_content(patience: &Patience) -> String {
+    format!(
+        "\nHSSSSSSS!!! TOO FAST!!! Wait {}ms between pets!\n",
+        patience.cooldown_ms
+    )
+}
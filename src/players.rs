@@ -0,0 +1,116 @@
+// Who petted what.
+
+use libc;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::mem;
+use std::ptr;
+
+// whoever is doing the petting
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Who {
+    pub uid: u32,
+    pub pid: u32,
+}
+
+// what a single pet did to a kitten
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Outcome {
+    StillNeedy,
+    AtPeace,
+    MadeMad,
+    AlreadyMad,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Pet {
+    pub uid: u32,
+    pub pid: u32,
+    pub inode: u64,
+    pub outcome: Outcome,
+    // seconds since the epoch
+    pub at: i64,
+}
+
+#[derive(Debug, Default)]
+pub struct PlayerStats {
+    pub uid: u32,
+    pub pets_given: u32,
+    pub brought_to_peace: u32,
+    pub made_mad: u32,
+}
+
+// best players first: most kittens at peace, then fewest made mad, then
+// whoever got there with fewer pets.
+pub fn player_stats(pets: &[Pet]) -> Vec<PlayerStats> {
+    let mut by_uid: BTreeMap<u32, PlayerStats> = BTreeMap::new();
+    for pet in pets.iter() {
+        let stats = by_uid.entry(pet.uid).or_insert_with(|| PlayerStats {
+            uid: pet.uid,
+            ..PlayerStats::default()
+        });
+        stats.pets_given += 1;
+        match pet.outcome {
+            Outcome::AtPeace => stats.brought_to_peace += 1,
+            Outcome::MadeMad => stats.made_mad += 1,
+            Outcome::StillNeedy | Outcome::AlreadyMad => {}
+        }
+    }
+    let mut players = by_uid.into_values().collect::<Vec<_>>();
+    players.sort_by_key(|p| (Reverse(p.brought_to_peace), p.made_mad, p.pets_given, p.uid));
+    players
+}
+
+pub fn user_name(uid: u32) -> String {
+    let mut buf = vec![0 as libc::c_char; 1024];
+    let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+    let mut result = ptr::null_mut();
+    let ret =
+        unsafe { libc::getpwuid_r(uid, &mut passwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if ret == 0 && !result.is_null() {
+        unsafe { CStr::from_ptr(passwd.pw_name) }
+            .to_string_lossy()
+            .into_owned()
+    } else {
+        format!("uid {}", uid)
+    }
+}
+
+pub fn breakdown(players: &[PlayerStats]) -> String {
+    if players.is_empty() {
+        return String::new();
+    }
+    let mut content = "\nWho did what:\n\n".to_string();
+    for player in players.iter() {
+        content.push_str(&format!(
+            "* {}: {} pets given, {} kitties brought to peace, {} kitties made mad\n",
+            user_name(player.uid),
+            player.pets_given,
+            player.brought_to_peace,
+            player.made_mad
+        ));
+    }
+    content
+}
+
+pub fn leaderboard_content(players: &[PlayerStats]) -> String {
+    if players.is_empty() {
+        return "LEADERBOARD\n\nNobody has petted a kitty yet!\n".to_string();
+    }
+    let mut content = format!(
+        "LEADERBOARD\n\n{:>3}  {:<16} {:>5} {:>5} {:>5}\n",
+        "#", "player", "peace", "mad", "pets"
+    );
+    for (i, player) in players.iter().enumerate() {
+        content.push_str(&format!(
+            "{:>3}  {:<16} {:>5} {:>5} {:>5}\n",
+            i + 1,
+            user_name(player.uid),
+            player.brought_to_peace,
+            player.made_mad,
+            player.pets_given
+        ));
+    }
+    content
+}
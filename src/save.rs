@@ -0,0 +1,222 @@
+// Saving and resuming a game.
+
+use serde_json::{self, Value};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use boss::Boss;
+use control::Difficulty;
+use events::LoggedEvent;
+use lesson::Lesson;
+use locks::Unlock;
+use patience::Patience;
+use players::Pet;
+use species::Species;
+use speedrun::Speedrun;
+use story::Story;
+use {file, Game, GameDir};
+
+pub const SAVE_VERSION: u64 = 1;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SavedFile {
+    inode: u64,
+    name: String,
+    content: String,
+    life: i32,
+    max_life: i32,
+    level_life: i32,
+    changed_at: i64,
+    species: Species,
+    script: Option<String>,
+    patience: Option<Patience>,
+    last_cared_ms: Option<i64>,
+    hissing: bool,
+    boss: Option<Boss>,
+    helpers: Vec<(u32, i64)>,
+    home: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SavedDir {
+    inode: u64,
+    name: String,
+    files: Vec<SavedFile>,
+    dirs: Vec<SavedDir>,
+    locked_until: Option<Unlock>,
+    unlocked_at: Option<i64>,
+}
+impl SavedDir {
+    pub fn snapshot(gamedir: &GameDir) -> SavedDir {
+        SavedDir {
+            inode: gamedir.inode,
+            name: gamedir.name.clone(),
+            files: gamedir
+                .files
+                .iter()
+                .map(|f| {
+                    let borrowed = f.borrow();
+                    SavedFile {
+                        inode: borrowed.inode,
+                        name: borrowed.name.clone(),
+                        content: borrowed.content.clone(),
+                        life: borrowed.life,
+                        max_life: borrowed.max_life,
+                        level_life: borrowed.level_life,
+                        changed_at: borrowed.changed_at,
+                        species: borrowed.species,
+                        script: borrowed.script.clone(),
+                        patience: borrowed.patience,
+                        last_cared_ms: borrowed.last_cared_ms,
+                        hissing: borrowed.hissing,
+                        boss: borrowed.boss,
+                        helpers: borrowed.helpers.clone(),
+                        home: borrowed.home,
+                    }
+                })
+                .collect(),
+            dirs: gamedir
+                .sub_dirs
+                .iter()
+                .map(|d| SavedDir::snapshot(&d.borrow()))
+                .collect(),
+            locked_until: gamedir.locked_until,
+            unlocked_at: gamedir.unlocked_at,
+        }
+    }
+
+    fn check_inodes(&self, seen: &mut HashSet<u64>) -> Result<(), String> {
+        let file_inodes = self.files.iter().map(|f| f.inode);
+        for inode in Some(self.inode).into_iter().chain(file_inodes) {
+            if !seen.insert(inode) {
+                return Err(format!("inode {} is used twice", inode));
+            }
+        }
+        for dir in self.dirs.iter() {
+            dir.check_inodes(seen)?;
+        }
+        Ok(())
+    }
+
+    fn to_game_dir(&self) -> GameDir {
+        GameDir {
+            inode: self.inode,
+            name: self.name.clone(),
+            files: self
+                .files
+                .iter()
+                .map(|f| {
+                    let game_file = file(f.inode, &f.name)
+                        .content(&f.content)
+                        .life(f.life)
+                        .max_life(f.max_life)
+                        .level_life(f.level_life)
+                        .changed_at(f.changed_at)
+                        .species(f.species)
+                        .script(f.script.clone())
+                        .patience(f.patience)
+                        .last_cared_ms(f.last_cared_ms)
+                        .hissing(f.hissing)
+                        .boss(f.boss)
+                        .helpers(f.helpers.clone())
+                        .home(f.home);
+                    Rc::new(RefCell::new(game_file))
+                })
+                .collect(),
+            sub_dirs: self
+                .dirs
+                .iter()
+                .map(|d| Rc::new(RefCell::new(d.to_game_dir())))
+                .collect(),
+            locked_until: self.locked_until,
+            unlocked_at: self.unlocked_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct SaveFile {
+    version: u64,
+    root: SavedDir,
+    pets: Vec<Pet>,
+    speedrun: Speedrun,
+    abandoned: u32,
+    dogs_chased: u32,
+    difficulty: Difficulty,
+    paused_at: Option<i64>,
+    events: Vec<LoggedEvent>,
+    story: Story,
+    lesson: Option<Lesson>,
+}
+
+pub fn parse(json: &str) -> Result<Game, String> {
+    let save: Value = serde_json::from_str(json).map_err(|e| format!("bad save: {}", e))?;
+    let version = save
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| "this doesn't look like a fuschia save (no version)".to_string())?;
+    if version != SAVE_VERSION {
+        return Err(format!(
+            "save is version {} but this fuschia only knows version {}",
+            version, SAVE_VERSION
+        ));
+    }
+
+    let save: SaveFile = serde_json::from_value(save).map_err(|e| format!("bad save: {}", e))?;
+    if save.root.inode != 1 {
+        return Err("the root directory has to be inode 1".to_string());
+    }
+    save.root.check_inodes(&mut HashSet::new())?;
+    let mut fs = Game::new(Rc::new(RefCell::new(save.root.to_game_dir())));
+    fs.pets = save.pets;
+    fs.speedrun = save.speedrun;
+    fs.abandoned = save.abandoned;
+    fs.dogs_chased = save.dogs_chased;
+    fs.difficulty = save.difficulty;
+    fs.paused_at = save.paused_at;
+    fs.story = save.story;
+    fs.lesson = save.lesson;
+    // anything that happened while starting back up goes after the rest
+    fs.events.splice(0..0, save.events);
+    Ok(fs)
+}
+
+pub fn load(path: &Path) -> Result<Game, String> {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+    parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// the save `parse` reads back in
+pub fn to_json(fs: &Game) -> Result<String, String> {
+    let save = SaveFile {
+        version: SAVE_VERSION,
+        root: SavedDir::snapshot(&fs.gamedir.borrow()),
+        pets: fs.pets.clone(),
+        speedrun: fs.speedrun,
+        abandoned: fs.abandoned,
+        dogs_chased: fs.dogs_chased,
+        difficulty: fs.difficulty,
+        paused_at: fs.paused_at,
+        events: fs.events.clone(),
+        story: fs.story.clone(),
+        lesson: fs.lesson.clone(),
+    };
+    serde_json::to_string_pretty(&save).map_err(|e| e.to_string())
+}
+
+// writes to a temporary file first so a crash mid-save can't eat the game
+pub fn store(fs: &Game, path: &Path) -> Result<(), String> {
+    let json = to_json(fs)?;
+    let tmp_path = path.with_extension("tmp");
+    File::create(&tmp_path)
+        .and_then(|mut f| f.write_all(json.as_bytes()))
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .map_err(|e| format!("can't save to {}: {}", path.display(), e))
+}
@@ -0,0 +1,175 @@
+// Kittens that behave however their level's script says.
+
+use rhai::{CallFnOptions, Dynamic, Engine, FuncArgs, Map, Scope, AST, INT};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use players::Outcome;
+use {Either, Game, GameFile};
+
+// so `loop {}` can't hang the mount
+const MAX_OPERATIONS: u64 = 100_000;
+
+pub struct Scripts {
+    engine: Engine,
+    // compiled scripts by source, including the ones that didn't compile
+    compiled: RefCell<HashMap<String, Result<Rc<AST>, String>>>,
+}
+impl Default for Scripts {
+    fn default() -> Scripts {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        Scripts {
+            engine: engine,
+            compiled: RefCell::new(HashMap::new()),
+        }
+    }
+}
+impl Scripts {
+    fn compile(&self, source: &str) -> Result<Rc<AST>, String> {
+        self.compiled
+            .borrow_mut()
+            .entry(source.to_string())
+            .or_insert_with(|| {
+                self.engine
+                    .compile(source)
+                    .map(Rc::new)
+                    .map_err(|e| e.to_string())
+            })
+            .clone()
+    }
+
+    // runs `hook` from `kitten`'s script, if it has one. `None` means
+    // there was nothing to run or the script had nothing to say.
+    fn call<A: FuncArgs>(
+        &self,
+        kitten: &GameFile,
+        hook: &str,
+        arity: usize,
+        args: A,
+    ) -> Result<Option<Dynamic>, String> {
+        let source = match kitten.script {
+            Some(ref source) => source,
+            None => return Ok(None),
+        };
+        let ast = self.compile(source)?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == hook && f.params.len() == arity)
+        {
+            return Ok(None);
+        }
+        // only the functions count, anything else in the script never runs
+        let options = CallFnOptions::new().eval_ast(false);
+        let result: Dynamic = self
+            .engine
+            .call_fn_with_options(options, &mut Scope::new(), &ast, hook, args)
+            .map_err(|e| format!("{}: {}", hook, e))?;
+        if result.is_unit() {
+            Ok(None)
+        } else {
+            Ok(Some(result))
+        }
+    }
+
+    fn call_for_life<A: FuncArgs>(
+        &self,
+        kitten: &GameFile,
+        hook: &str,
+        arity: usize,
+        args: A,
+    ) -> Result<Option<i32>, String> {
+        match self.call(kitten, hook, arity, args)? {
+            Some(life) => {
+                let life = life
+                    .as_int()
+                    .map_err(|t| format!("{} has to return a number, not {}", hook, t))?;
+                i32::try_from(life)
+                    .map(Some)
+                    .map_err(|_| format!("{}: {} is too many pets", hook, life))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // gives `kitten` a `verb`, the way its script (if any) says to
+    pub fn pet(&self, kitten: &mut GameFile, verb: &str, now: i64) -> Outcome {
+        let args = (kitten_map(kitten, now), verb.to_string());
+        match self.call_for_life(kitten, "on_pet", 2, args) {
+            Ok(Some(life)) => {
+                kitten.script_error = None;
+                kitten.set_life(life, now)
+            }
+            Ok(None) => kitten.pet(now),
+            Err(e) => {
+                kitten.script_error = Some(e);
+                kitten.pet(now)
+            }
+        }
+    }
+
+    // whether `on_tick` changed how needy `kitten` is
+    pub fn tick(&self, kitten: &mut GameFile, now: i64) -> bool {
+        let args = (kitten_map(kitten, now),);
+        match self.call_for_life(kitten, "on_tick", 1, args) {
+            Ok(Some(life)) => {
+                kitten.script_error = None;
+                let before = kitten.life;
+                kitten.set_life(life, now);
+                kitten.life != before
+            }
+            Ok(None) => false,
+            Err(e) => {
+                kitten.script_error = Some(e);
+                false
+            }
+        }
+    }
+
+    // what `kitten`'s file says
+    pub fn content(&self, kitten: &GameFile, now: i64) -> String {
+        let args = (kitten_map(kitten, now),);
+        let content = self.call(kitten, "on_read", 1, args).and_then(|c| match c {
+            Some(content) => content
+                .into_string()
+                .map(Some)
+                .map_err(|t| format!("on_read has to return a string, not {}", t)),
+            None => Ok(None),
+        });
+        match content {
+            Ok(Some(content)) => content,
+            Ok(None) => kitten.get_content(),
+            Err(e) => format!("{}{}", kitten.get_content(), error_content(&e)),
+        }
+    }
+}
+
+pub fn error_content(error: &str) -> String {
+    format!("\nMy script is broken :(\n\n{}\n", error)
+}
+
+fn kitten_map(kitten: &GameFile, now: i64) -> Map {
+    let mut map = Map::new();
+    map.insert("name".into(), Dynamic::from(kitten.name.clone()));
+    map.insert(
+        "species".into(),
+        Dynamic::from(kitten.species.name().to_string()),
+    );
+    map.insert("life".into(), Dynamic::from(INT::from(kitten.life)));
+    map.insert("max_life".into(), Dynamic::from(INT::from(kitten.max_life)));
+    map.insert("idle".into(), Dynamic::from(now - kitten.changed_at));
+    map
+}
+
+impl Game {
+    // whether any kitten's `on_tick` changed how needy it is
+    pub fn tick_scripts(&mut self, now: i64) -> bool {
+        let mut changed = false;
+        for kitten in self.inode_table.values().filter_map(Either::kitten) {
+            changed |= self.scripts.tick(&mut kitten.borrow_mut(), now);
+        }
+        changed
+    }
+}
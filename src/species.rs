@@ -0,0 +1,239 @@
+// Different kinds of kittens want different kinds of care.
+
+use std::cmp;
+
+pub const VERBS: &[&str] = &["pets", "treats", "brush"];
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Species {
+    #[default]
+    Kitten,
+    Tabby,
+    Persian,
+    Siamese,
+}
+
+const KITTEN_NEEDY: &str = r#"                           __ _..._ _
+                           \ `)    `(/
+                           /`       \
+                           |   d  b  |
+             .-"````"=-..--\=    Y  /=
+           /`               `-.__=.'
+    _     / /\                 /o
+   ( \   / / |                 |
+    \ '-' /   >    /`""--.    /
+     '---'   /    ||      |   \\
+             \___,,))      \_,,))
+"#;
+
+const KITTEN_HAPPY: &str = r#"     _ _..._ __
+    \)`    (` /
+     /      `\
+    |  d  b   |
+    =\  Y    =/--..-="````"-.
+      '.=__.-'               `\
+         o/                 /\ \
+          |                 | \ \   / )
+           \    .--""`\    <   \ '-' /
+          //   |      ||    \   '---'
+         ((,,_/      ((,,___/
+"#;
+
+const KITTEN_MAD: &str = r#"      ,-~-,       ,-~~~~-,    /\  /\
+(\   / ,-, \    ,'        ', /  ~~  \
+ \'-' /   \ \  /     _      #  <0 0> \
+  '--'     \ \/    .' '.    # =  Y  =/
+            \     / \   \   `#-..!.-'
+             \   \   \   `\ \\
+              )  />  /     \ \\
+             / /`/ /`__     \ \\__
+            (____)))_)))     \__)))
+"#;
+
+const TABBY_NEEDY: &str = r#"        /\_/\
+       ( o.o )
+        > ^ <      _________
+       /  -  \     \  ...  /
+      / ----- \     \_____/
+     (  -----  )
+      \__| |__/
+"#;
+
+const TABBY_HAPPY: &str = r#"        /\_/\
+       ( ^.^ )   *burp*
+        > w <
+      /       \
+     (    ~    )
+     (         )
+      \__| |__/
+"#;
+
+const TABBY_MAD: &str = r#"        /\_/\
+       ( x.x )
+        > n <
+      /       \
+     (   @@@   )
+     (         )
+      \__| |__/
+"#;
+
+const PERSIAN_NEEDY: &str = r#"      /\~~~~~/\
+    ~( ~ o o ~ )~
+   ~~~(   v   )~~~
+  ~~~~/~~~~~~~\~~~~
+ ~~~~(~~~~~~~~~)~~~~
+      \_/   \_/
+"#;
+
+const PERSIAN_HAPPY: &str = r#"      /\     /\      *
+     (   ^ ^   )  *
+      (   w   )       *
+      /       \
+     (         )
+      \_/   \_/
+"#;
+
+const PERSIAN_MAD: &str = r#"      /\     /\
+     (   > <   )
+      (   ^   )
+      / .   . \
+     (  .   .  )
+      \_/   \_/
+"#;
+
+const SIAMESE_NEEDY: &str = r#"       /\_/\
+      ( -.- )   hmph.
+       > ^ <
+      /  |  \
+     (___|___)
+"#;
+
+const SIAMESE_HAPPY: &str = r#"       /\_/\
+      ( ^o^ )   ~purr~
+       > ^ <
+      /  |  \
+     (___|___)
+"#;
+
+const SIAMESE_MAD: &str = r#"       /\_/\
+      ( >_< )   HISS
+       > ^ <
+      /  |  \
+     (___|___)
+"#;
+
+fn content(name: &str, message: &str, art: &str) -> String {
+    format!(
+        "Hello StarCon!\nMy name is: {}\n\n{}\n{}\n",
+        name, message, art
+    )
+}
+
+impl Species {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Species::Kitten => "kitten",
+            Species::Tabby => "tabby",
+            Species::Persian => "persian",
+            Species::Siamese => "siamese",
+        }
+    }
+
+    pub fn verbs(&self) -> &'static [&'static str] {
+        match self {
+            Species::Kitten => &["pets"],
+            Species::Tabby => &["treats"],
+            Species::Persian => &["brush"],
+            Species::Siamese => &["brush", "treats", "pets"],
+        }
+    }
+
+    // the verb a kitten that's already been cared for `cares_given` times
+    // wants next
+    pub fn wants(&self, cares_given: i32) -> &'static str {
+        let verbs = self.verbs();
+        verbs[cmp::max(cares_given, 0) as usize % verbs.len()]
+    }
+
+    pub fn needy_content(&self, name: &str, needed: i32, next: &str) -> String {
+        match self {
+            Species::Kitten => content(
+                name,
+                &format!("I NEED TO BE PETTED\n\nPlease send me {} pets", needed),
+                KITTEN_NEEDY,
+            ),
+            Species::Tabby => content(
+                name,
+                &format!("I AM SO HUNGRY\n\nPlease send me {} treats", needed),
+                TABBY_NEEDY,
+            ),
+            Species::Persian => content(
+                name,
+                &format!("MY FUR IS ALL TANGLED\n\nPlease brush me {} more times", needed),
+                PERSIAN_NEEDY,
+            ),
+            Species::Siamese => content(
+                name,
+                &format!(
+                    "I AM VERY PARTICULAR\n\nI like to be brushed, then get treats, then pets.\nI still need {} more, and next I want: {}",
+                    needed, next
+                ),
+                SIAMESE_NEEDY,
+            ),
+        }
+    }
+
+    pub fn happy_content(&self, name: &str) -> String {
+        match self {
+            Species::Kitten => content(
+                name,
+                "WOW! YOU GAVE ME ENOUGH PETS!! ❤❤❤❤❤❤❤\n",
+                KITTEN_HAPPY,
+            ),
+            Species::Tabby => content(
+                name,
+                "NOM NOM NOM! MY BELLY IS FULL!! ❤❤❤❤❤❤❤\n",
+                TABBY_HAPPY,
+            ),
+            Species::Persian => content(
+                name,
+                "I AM SO FLUFFY AND BEAUTIFUL!! ❤❤❤❤❤❤❤\n",
+                PERSIAN_HAPPY,
+            ),
+            Species::Siamese => content(
+                name,
+                "FINALLY, SOMEONE WHO UNDERSTANDS ME ❤❤❤❤❤❤❤\n",
+                SIAMESE_HAPPY,
+            ),
+        }
+    }
+
+    pub fn mad_content(&self, name: &str) -> String {
+        match self {
+            Species::Kitten => content(name, "MY HEART IS FICKLE! NO MORE PETS!!!!\n", KITTEN_MAD),
+            Species::Tabby => content(name, "TOO MANY TREATS! MY TUMMY HURTS!!!!\n", TABBY_MAD),
+            Species::Persian => content(name, "STOP BRUSHING ME! I'LL GO BALD!!!!\n", PERSIAN_MAD),
+            Species::Siamese => content(
+                name,
+                "YOU DID IT ALL WRONG! I'M NEVER SPEAKING TO YOU AGAIN!!!!\n",
+                SIAMESE_MAD,
+            ),
+        }
+    }
+}
+
+// Splits written data into care verbs, one per non-empty line, so `echo`,
+// `printf` and `tee` all work. Returns `None` if anything written isn't a
+// verb.
+pub fn parse_verbs(data: &[u8]) -> Option<Vec<&str>> {
+    let string = ::std::str::from_utf8(data).ok()?;
+    let mut verbs = Vec::new();
+    for line in string.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        if !VERBS.contains(&line) {
+            return None;
+        }
+        verbs.push(line);
+    }
+    Some(verbs)
+}
@@ -0,0 +1,83 @@
+// Speedrun timing and scoring.
+
+use std::cell::Cell;
+use time;
+
+use players::{Outcome, Pet};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct Speedrun {
+    pub started_at: Option<i64>,
+    pub ended_at: Option<i64>,
+    pub writes: u32,
+}
+impl Speedrun {
+    pub fn start(&mut self, now: i64) {
+        if self.started_at.is_none() {
+            self.started_at = Some(now);
+        }
+    }
+
+    pub fn finish(&mut self, now: i64) {
+        if self.started_at.is_some() && self.ended_at.is_none() {
+            self.ended_at = Some(now);
+        }
+    }
+
+    pub fn elapsed(&self, now: i64) -> i64 {
+        match self.started_at {
+            Some(started_at) => self.ended_at.unwrap_or(now) - started_at,
+            None => 0,
+        }
+    }
+}
+
+thread_local! {
+    // what time a replay says it is
+    static STOPPED_AT: Cell<Option<i64>> = const { Cell::new(None) };
+}
+
+// milliseconds since the epoch, unless the clock's been stopped
+pub fn now_ms() -> i64 {
+    STOPPED_AT.with(|t| t.get()).unwrap_or_else(|| {
+        let now = time::get_time();
+        now.sec * 1000 + i64::from(now.nsec) / 1_000_000
+    })
+}
+
+// runs `f` with the clock stopped at `at_ms`, so everything it does happens
+// at the same moment. A replay runs each request at the time it was
+// recorded.
+pub fn stopped_at<T, F: FnOnce() -> T>(at_ms: i64, f: F) -> T {
+    let before = STOPPED_AT.with(|t| t.replace(Some(at_ms)));
+    let result = f();
+    STOPPED_AT.with(|t| t.set(before));
+    result
+}
+
+// pets given to a kitten that was already at peace (or already mad)
+pub fn wasted_pets(pets: &[Pet]) -> u32 {
+    pets.iter()
+        .filter(|p| p.outcome == Outcome::MadeMad || p.outcome == Outcome::AlreadyMad)
+        .count() as u32
+}
+
+pub fn format_elapsed(ms: i64) -> String {
+    format!("{}:{:02}.{:03}", ms / 60_000, (ms / 1000) % 60, ms % 1000)
+}
+
+// every kitten at peace is worth 1000 points. Wasted pets, writes,
+// abandoned kittens and seconds on the clock all cost you.
+pub fn score(
+    kitties_at_peace: u32,
+    wasted_pets: u32,
+    writes: u32,
+    abandoned: u32,
+    elapsed_ms: i64,
+) -> i64 {
+    1000 * i64::from(kitties_at_peace)
+        - 100 * i64::from(wasted_pets)
+        - 10 * i64::from(writes)
+        - 500 * i64::from(abandoned)
+        - elapsed_ms / 1000
+}
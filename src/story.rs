@@ -0,0 +1,119 @@
+// Story mode, for levels that unfold a chapter at a time.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use events::Event;
+use level::DirSpec;
+use {Either, Game, GameDir};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Chapter {
+    pub story: String,
+    pub dir: DirSpec,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Story {
+    prologue: Option<String>,
+    chapters: Vec<Chapter>,
+    // the directory each chapter so far turned up as, by inode
+    opened: Vec<u64>,
+}
+impl Story {
+    pub fn new(prologue: Option<String>, chapters: Vec<Chapter>) -> Story {
+        Story {
+            prologue: prologue,
+            chapters: chapters,
+            opened: Vec::new(),
+        }
+    }
+
+    // the story so far, by chapter number
+    pub fn pages(&self) -> Vec<(usize, String)> {
+        let prologue = self.prologue.iter().map(|p| (1, p.clone()));
+        let chapters = self
+            .chapters
+            .iter()
+            .take(self.opened.len())
+            .enumerate()
+            .map(|(i, c)| (i + 2, c.story.clone()));
+        prologue.chain(chapters).collect()
+    }
+
+    fn current(&self) -> u64 {
+        self.opened.last().cloned().unwrap_or(1)
+    }
+}
+
+fn all_at_peace(dir: &GameDir) -> bool {
+    dir.files.iter().all(|f| {
+        let f = f.borrow();
+        !f.is_kitten() || f.life == 0
+    }) && dir.sub_dirs.iter().all(|d| all_at_peace(&d.borrow()))
+}
+
+fn name_taken(dir: &GameDir, name: &str) -> bool {
+    dir.files.iter().any(|f| f.borrow().name == name)
+        || dir.sub_dirs.iter().any(|d| d.borrow().name == name)
+}
+
+// takes the directory `inode` out of `dir` (or wherever it is under it)
+fn tear_out(dir: &Rc<RefCell<GameDir>>, inode: u64) {
+    let mut borrowed = dir.borrow_mut();
+    borrowed.sub_dirs.retain(|d| d.borrow().inode != inode);
+    for sub_dir in borrowed.sub_dirs.iter() {
+        tear_out(sub_dir, inode);
+    }
+}
+
+impl Game {
+    // starts the next chapter, for as long as the current one is done
+    pub fn turn_pages(&mut self) {
+        while self.story.opened.len() < self.story.chapters.len() {
+            let here = match self.inode_table.get(&self.story.current()) {
+                Some(Either::Directory { dir }) => Rc::clone(dir),
+                _ => return,
+            };
+            if !all_at_peace(&here.borrow()) {
+                return;
+            }
+            let chapter = self.story.chapters[self.story.opened.len()].dir.clone();
+            let dir = chapter.build_sub_dir(self.next_inode());
+            // somebody might have adopted a kitten by the same name
+            let mut name = chapter.name.clone();
+            let mut n = 2;
+            while name_taken(&here.borrow(), &name) {
+                name = format!("{}-{}", chapter.name, n);
+                n += 1;
+            }
+            dir.borrow_mut().name = name.clone();
+            let inode = dir.borrow().inode;
+            self.graft(&here, dir);
+            self.story.opened.push(inode);
+            self.log(Event::ChapterBegan {
+                chapter: self.story.opened.len() + 1,
+                dir: name,
+            });
+        }
+    }
+
+    // takes every chapter back out, for a reset
+    pub fn close_book(&mut self) {
+        for inode in self.story.opened.drain(..) {
+            tear_out(&self.gamedir, inode);
+        }
+        self.reindex();
+    }
+}
+
+pub fn story_content(pages: &[(usize, String)]) -> String {
+    if pages.is_empty() {
+        return String::new();
+    }
+    let mut content = "\nThe story so far:\n".to_string();
+    for (chapter, page) in pages.iter() {
+        content.push_str(&format!("\nChapter {}\n\n{}\n", chapter, page.trim_end()));
+    }
+    content
+}
@@ -0,0 +1,354 @@
+use libc::{EACCES, EAGAIN, EEXIST, EISDIR, ENOENT, ENOTSUP};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::{env, fs, process};
+
+use driver::{self, pet, play, starcon, ME};
+use generate::{self, Settings};
+use level::DirSpec;
+use needs::Needs;
+use players::Outcome;
+use save;
+use speedrun;
+use wander::{Wander, Wandering};
+use xattrs;
+use {file, Game, GameDir, GameFile};
+
+// the names in the directory at `path`, without anything ticking
+fn list(game: &Game, path: &str) -> Vec<String> {
+    let children = &game.parent_table[&game.resolve(path).unwrap()];
+    children.iter().map(|c| c.name()).collect()
+}
+
+#[test]
+fn pets_say_what_they_did() {
+    let mut game = starcon();
+    for _ in 0..4 {
+        assert_eq!(game.pet("3.txt", ME), Ok(Outcome::StillNeedy));
+    }
+    assert_eq!(game.pet("3.txt", ME), Ok(Outcome::AtPeace));
+    assert_eq!(game.pet("3.txt", ME), Ok(Outcome::MadeMad));
+    assert_eq!(game.pet("/3.txt", ME), Ok(Outcome::AlreadyMad));
+}
+
+#[test]
+fn only_kittens_can_be_petted() {
+    let mut game = starcon();
+    assert_eq!(game.pet("LiveJournal.txt", ME), Err(EACCES));
+    assert_eq!(game.pet("xxx", ME), Err(EISDIR));
+    assert_eq!(game.pet("xxx/nope.txt", ME), Err(ENOENT));
+}
+
+#[test]
+fn status_tallies_every_kitten() {
+    let mut game = starcon();
+    assert_eq!(game.status().tally.needing_pets, 5);
+    pet(&mut game, "3.txt", 5);
+    pet(&mut game, "lskdjf/10.txt", 6);
+    let status = game.status();
+    assert_eq!(status.tally.needing_pets, 3);
+    assert_eq!(status.tally.at_peace, 1);
+    assert_eq!(status.tally.mad, 1);
+    assert!(!status.is_game_over());
+    assert_eq!(status.players.len(), 1);
+
+    pet(&mut game, "xxx/5.txt", 5);
+    pet(&mut game, "xxx/6.txt", 5);
+    pet(&mut game, "xxx/xxxxx/8.txt", 5);
+    assert!(game.status().is_game_over());
+    assert!(game.status().to_content().contains("SCORE:"));
+}
+
+#[test]
+fn the_game_isnt_over_while_a_kitten_has_got_lonely_again() {
+    let at = |secs: i64| 1_500_000_000_000 + secs * 1000;
+    let mut game = play(
+        r#"{
+          "name": "pair",
+          "kittens": [{ "name": "a.txt", "life": 1 }, { "name": "b.txt", "life": 1 }]
+        }"#,
+    );
+    game.needs.lonely_after = Some(60);
+    speedrun::stopped_at(at(0), || {
+        game.lookup(1, "a.txt", ME).unwrap();
+        pet(&mut game, "a.txt", 1);
+    });
+    speedrun::stopped_at(at(120), || pet(&mut game, "b.txt", 1));
+    assert!(!game.status().is_game_over());
+    assert_eq!(game.speedrun.ended_at, None);
+}
+
+#[test]
+fn locked_rooms_open_once_the_room_outside_is_at_peace() {
+    let mut game = starcon();
+    assert_eq!(game.pet("xxx/xxxxx/8.txt", ME), Err(EACCES));
+    assert_eq!(game.status().locked_rooms.len(), 1);
+    pet(&mut game, "xxx/5.txt", 5);
+    assert_eq!(game.pet("xxx/xxxxx/8.txt", ME), Err(EACCES));
+    pet(&mut game, "xxx/6.txt", 5);
+    assert_eq!(game.pet("xxx/xxxxx/8.txt", ME), Ok(Outcome::StillNeedy));
+    assert!(game.status().locked_rooms.is_empty());
+
+    // and they stay open, even if a kitten outside gets needy again
+    let xxx = game.resolve("xxx").unwrap();
+    game.create(xxx, "new.txt").unwrap();
+    assert_eq!(game.pet("xxx/xxxxx/8.txt", ME), Ok(Outcome::StillNeedy));
+}
+
+#[test]
+fn saves_load_back_as_the_same_game() {
+    let mut game = starcon();
+    pet(&mut game, "3.txt", 2);
+    pet(&mut game, "xxx/5.txt", 6);
+    game.create(1, "new.txt").unwrap();
+    let json = save::to_json(&game).unwrap();
+    let mut loaded = save::parse(&json).unwrap();
+    assert_eq!(save::to_json(&loaded).unwrap(), json);
+    assert_eq!(loaded.status().tally.mad, 1);
+    assert_eq!(loaded.pet("3.txt", ME), Ok(Outcome::StillNeedy));
+    assert!(loaded.resolve("new.txt").is_ok());
+}
+
+#[test]
+fn saves_from_another_version_are_turned_away() {
+    let json = save::to_json(&starcon()).unwrap();
+    let version = format!("\"version\": {}", save::SAVE_VERSION);
+    assert!(json.contains(&version));
+    let newer = json.replace(
+        &version,
+        &format!("\"version\": {}", save::SAVE_VERSION + 1),
+    );
+    assert!(save::parse(&newer).is_err());
+    let unversioned = json.replace(&format!("{},", version), "");
+    assert!(save::parse(&unversioned).is_err());
+    assert!(save::parse(&json).is_ok());
+}
+
+#[test]
+fn a_hiss_is_saved_too() {
+    let mut game = driver::impatient("hiss");
+    let path = env::temp_dir().join(format!("fuschia-test-{}-hiss.json", process::id()));
+    game.save_file = Some(path.clone());
+    let cat = game.resolve("cat").unwrap();
+    assert_eq!(game.write(cat, b"pets\npets\n", ME), Err(EAGAIN));
+    let saved = save::load(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(save::to_json(&saved.unwrap())
+        .unwrap()
+        .contains("\"hissing\": true"));
+}
+
+// every directory and file in a generated level, as "<inode> <path>"
+fn layout(seed: u64) -> Vec<String> {
+    fn walk(dir: &GameDir, path: &str, layout: &mut Vec<String>) {
+        layout.push(format!("{} {}/", dir.inode, path));
+        for file in dir.files.iter() {
+            let file = file.borrow();
+            layout.push(format!("{} {}/{}", file.inode, path, file.name));
+        }
+        for sub_dir in dir.sub_dirs.iter() {
+            let sub_dir = sub_dir.borrow();
+            walk(&sub_dir, &format!("{}/{}", path, sub_dir.name), layout);
+        }
+    }
+    let root = generate::generate(seed, &Settings::default()).build();
+    let mut layout = Vec::new();
+    walk(&root.borrow(), "", &mut layout);
+    layout
+}
+
+#[test]
+fn the_same_seed_makes_the_same_level() {
+    assert_eq!(layout(42), layout(42));
+    assert_ne!(layout(42), layout(43));
+}
+
+// if this changes, every seed anybody has shared changes with it
+const SEED_42: &str = "\
+1 /
+2 /LiveJournal.txt
+3 /leaderboard.txt
+4 /events.log
+5 /.events
+6 /whiskers.kitty
+7 /aacj/
+8 /aacj/sdvczs/
+9 /aacj/sdvczs/hqx/
+10 /aacj/sdvczs/hqx/oreo.kitty
+11 /aacj/jtxsdq/
+12 /aacj/jtxsdq/nala.kitty
+13 /aacj/jtxsdq/kfkg/
+14 /aacj/jtxsdq/gskehw/
+15 /whwkedj/
+16 /whwkedj/vqzgtk/
+17 /whwkedj/vqzgtk/mittens.kitty
+18 /whwkedj/vqzgtk/rwgjrwg/
+19 /whwkedj/vqzgtk/fwdhd/
+20 /whwkedj/vqzgtk/fwdhd/tom.kitty
+21 /whwkedj/kjdz/
+22 /whwkedj/kjdz/luna.kitty
+23 /whwkedj/kjdz/lcq/
+24 /whwkedj/kjdz/qgfvj/
+";
+
+#[test]
+fn seed_42_makes_this_level() {
+    assert_eq!(layout(42).join("\n") + "\n", SEED_42);
+}
+
+#[test]
+fn kittens_get_lonely_and_calm_down_over_time() {
+    let needs = Needs {
+        lonely_after: Some(60),
+        cooldown: Some(30),
+    };
+    let mut cat = file(2, "cat.txt").life(2).changed_at(0);
+    let life_at = |cat: &mut GameFile, now| {
+        cat.catch_up(now, &needs);
+        cat.life
+    };
+
+    cat.pet(0);
+    cat.pet(0);
+    assert_eq!(life_at(&mut cat, 59), 0);
+    assert_eq!(life_at(&mut cat, 60), 1);
+    assert_eq!(life_at(&mut cat, 120), 2);
+    // never needier than at the start
+    assert_eq!(life_at(&mut cat, 600), 2);
+
+    for _ in 0..3 {
+        cat.pet(600);
+    }
+    assert_eq!(life_at(&mut cat, 629), -1);
+    assert_eq!(life_at(&mut cat, 630), 0);
+}
+
+#[test]
+fn generated_kittens_can_hide_and_be_called_anything() {
+    fn kittens(dir: &DirSpec, names: &mut Vec<String>) {
+        names.extend(dir.kittens.iter().map(|k| k.name.clone()));
+        for sub_dir in dir.dirs.iter() {
+            kittens(sub_dir, names);
+        }
+    }
+    let settings = Settings {
+        extension: "cat".to_string(),
+        hidden: 2,
+        ..Settings::default()
+    };
+    let mut names = Vec::new();
+    kittens(&generate::generate(42, &settings), &mut names);
+    assert_eq!(names.len(), 6);
+    assert!(names.iter().all(|name| name.ends_with(".cat")));
+    assert_eq!(names.iter().filter(|name| name.starts_with('.')).count(), 2);
+}
+
+const HIDING: &str = r#"{
+    "name": "hiding",
+    "kittens": [{ "name": "cat.kitty", "life": 1 }],
+    "dirs": [{ "name": "attic", "kittens": [{ "name": ".shy.kitty", "life": 1 }] }]
+}"#;
+
+#[test]
+fn hidden_kittens_are_counted_but_not_given_away() {
+    let mut game = play(HIDING);
+    let journal = game.status().to_content();
+    assert!(journal.contains("I can hear 1 kitty meowing but I can't see it anywhere..."));
+    assert!(!journal.contains("attic") && !journal.contains(".shy"));
+
+    pet(&mut game, "attic/.shy.kitty", 1);
+    let journal = game.status().to_content();
+    assert!(!journal.contains("meowing"));
+    assert!(journal.contains("* 1 kitties still need pets"));
+}
+
+#[test]
+fn xattrs_say_how_a_kitten_is_doing() {
+    let mut game = starcon();
+    let ino = game.resolve("3.txt").unwrap();
+    let xattrs = |game: &Game| {
+        game.xattrs(ino)
+            .unwrap()
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+    };
+    let before = xattrs(&game);
+    assert_eq!(before["user.fuschia.life"], "5");
+    assert_eq!(before["user.fuschia.state"], "needy");
+    assert_eq!(before["user.fuschia.pets_received"], "0");
+    assert_eq!(before["user.fuschia.last_petted"], "never");
+
+    pet(&mut game, "3.txt", 5);
+    let after = xattrs(&game);
+    assert_eq!(after["user.fuschia.life"], "0");
+    assert_eq!(after["user.fuschia.state"], "peace");
+    assert_eq!(after["user.fuschia.pets_received"], "5");
+    assert!(after["user.fuschia.last_petted"].ends_with('Z'));
+
+    // only kittens have any, and nobody gets to set them
+    let xxx = game.resolve("xxx").unwrap();
+    assert_eq!(game.xattrs(xxx), Ok(Vec::new()));
+    assert_eq!(game.xattrs(9999), Err(ENOENT));
+    assert_eq!(xattrs::read_only(OsStr::new("user.fuschia.life")), EACCES);
+    assert_eq!(xattrs::read_only(OsStr::new("user.other")), ENOTSUP);
+}
+
+#[test]
+fn needy_kittens_wander_off_and_the_journal_drops_a_hint() {
+    let mut game = play(
+        r#"{
+          "name": "house",
+          "kittens": [{ "name": "cat.txt", "life": 2 }, { "name": "calm.txt", "life": 0 }],
+          "dirs": [{ "name": "den" }]
+        }"#,
+    );
+    let every_other_op = Wandering {
+        every_secs: None,
+        every_ops: Some(2),
+    };
+    game.wander = Wander::new(every_other_op, 7);
+
+    game.tick();
+    assert!(list(&game, "den").is_empty());
+    game.tick();
+    let house = list(&game, "/");
+    assert!(!house.contains(&"cat.txt".to_string()) && house.contains(&"calm.txt".to_string()));
+    assert_eq!(list(&game, "den"), vec!["cat.txt"]);
+    let journal = game.status().to_content();
+    assert!(journal.contains("* cat.txt crept somewhere deeper"));
+
+    // and it stays found once it's at peace
+    pet(&mut game, "den/cat.txt", 2);
+    game.tick();
+    game.tick();
+    assert_eq!(list(&game, "den"), vec!["cat.txt"]);
+    let journal = game.status().to_content();
+    assert!(!journal.contains("crept somewhere deeper"));
+}
+
+#[test]
+fn adopted_kittens_need_pets_and_released_ones_are_missed() {
+    let mut game = starcon();
+    let stat = game.create(1, "new.txt").unwrap();
+    assert_eq!(game.resolve("new.txt"), Ok(stat.ino));
+    assert_eq!(game.status().tally.needing_pets, 6);
+    assert_eq!(game.create(1, "new.txt"), Err(EEXIST));
+    assert_eq!(game.create(1, "LiveJournal.txt").map(|_| ()), Err(EACCES));
+
+    assert_eq!(game.unlink(1, "new.txt"), Ok(()));
+    assert_eq!(game.resolve("new.txt"), Err(ENOENT));
+    assert!(game
+        .status()
+        .to_content()
+        .contains("* 1 kitty was abandoned before it was at peace :'("));
+    assert_eq!(game.unlink(1, "LiveJournal.txt"), Err(EACCES));
+    assert_eq!(game.unlink(1, "xxx"), Err(EISDIR));
+
+    // one at peace isn't abandoned
+    game.create(1, "calm.txt").unwrap();
+    pet(&mut game, "calm.txt", 5);
+    game.unlink(1, "calm.txt").unwrap();
+    assert_eq!(game.status().abandoned, 1);
+    // and a new kitten never gets an old one's inode
+    assert!(game.create(1, "new.txt").unwrap().ino > stat.ino);
+}
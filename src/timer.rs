@@ -0,0 +1,55 @@
+// Things that happen every so often, like kittens wandering off and dogs
+// turning up.
+//
+// Nothing runs in the background: a `Timer` is asked on every operation
+// whether it's time yet, either because enough seconds went by or because
+// enough operations did, so nothing happens while nobody is poking at the
+// file-system (or while the game is paused). The clock starts at the first
+// operation.
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Every {
+    pub every_secs: Option<i64>,
+    pub every_ops: Option<u32>,
+}
+
+pub struct Timer {
+    pub settings: Every,
+    last: Option<i64>,
+    ops: u32,
+}
+impl Timer {
+    pub fn new(settings: Every) -> Timer {
+        Timer {
+            settings: settings,
+            last: None,
+            ops: 0,
+        }
+    }
+
+    // counts an operation and says whether it's time
+    pub fn due(&mut self, now: i64) -> bool {
+        self.ops += 1;
+        let last = *self.last.get_or_insert(now);
+        let by_time = match self.settings.every_secs {
+            Some(secs) => now - last >= secs,
+            None => false,
+        };
+        let by_ops = match self.settings.every_ops {
+            Some(ops) => self.ops >= ops,
+            None => false,
+        };
+        if by_time || by_ops {
+            self.last = Some(now);
+            self.ops = 0;
+        }
+        by_time || by_ops
+    }
+
+    // pushes the clock back, for time spent paused
+    pub fn postpone(&mut self, secs: i64) {
+        if let Some(ref mut last) = self.last {
+            *last += secs;
+        }
+    }
+}
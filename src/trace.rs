@@ -0,0 +1,668 @@
+// Recording and replaying file-system operations.
+
+use fuse::{
+    FileAttr, FileType, ReplyAttr, ReplyCreate, ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry,
+    ReplyOpen, ReplyWrite, Request,
+};
+use libc::{EIO, ENOSYS};
+use serde_json;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use time::Timespec;
+
+use speedrun;
+
+// who sent a request
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Caller {
+    pub uid: u32,
+    pub gid: u32,
+    pub pid: u32,
+}
+impl<'a, 'b> From<&'b Request<'a>> for Caller {
+    fn from(req: &'b Request<'a>) -> Caller {
+        Caller {
+            uid: req.uid(),
+            gid: req.gid(),
+            pid: req.pid(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Op {
+    Lookup {
+        parent: u64,
+        name: String,
+    },
+    Getattr {
+        ino: u64,
+    },
+    Read {
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+    },
+    Readdir {
+        ino: u64,
+        fh: u64,
+        offset: i64,
+    },
+    Write {
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        data: Vec<u8>,
+        flags: u32,
+    },
+    Open {
+        ino: u64,
+        flags: u32,
+    },
+    Create {
+        parent: u64,
+        name: String,
+        mode: u32,
+        flags: u32,
+    },
+    Mknod {
+        parent: u64,
+        name: String,
+        mode: u32,
+        rdev: u32,
+    },
+    Unlink {
+        parent: u64,
+        name: String,
+    },
+    // only the size: nothing here looks at the rest
+    Setattr {
+        ino: u64,
+        size: Option<u64>,
+    },
+}
+impl Op {
+    pub fn lookup(parent: u64, name: &OsStr) -> Op {
+        Op::Lookup {
+            parent: parent,
+            name: name.to_string_lossy().into_owned(),
+        }
+    }
+
+    pub fn create(parent: u64, name: &OsStr, mode: u32, flags: u32) -> Op {
+        Op::Create {
+            parent: parent,
+            name: name.to_string_lossy().into_owned(),
+            mode: mode,
+            flags: flags,
+        }
+    }
+
+    pub fn mknod(parent: u64, name: &OsStr, mode: u32, rdev: u32) -> Op {
+        Op::Mknod {
+            parent: parent,
+            name: name.to_string_lossy().into_owned(),
+            mode: mode,
+            rdev: rdev,
+        }
+    }
+
+    pub fn unlink(parent: u64, name: &OsStr) -> Op {
+        Op::Unlink {
+            parent: parent,
+            name: name.to_string_lossy().into_owned(),
+        }
+    }
+
+    fn apply<F: Ops>(&self, fs: &mut F, caller: &Caller) -> Reply {
+        match *self {
+            Op::Lookup { parent, ref name } => fs.lookup(caller, parent, OsStr::new(name)),
+            Op::Getattr { ino } => fs.getattr(caller, ino),
+            Op::Read {
+                ino,
+                fh,
+                offset,
+                size,
+            } => fs.read(caller, ino, fh, offset, size),
+            Op::Readdir { ino, fh, offset } => fs.readdir(caller, ino, fh, offset),
+            Op::Write {
+                ino,
+                fh,
+                offset,
+                ref data,
+                flags,
+            } => fs.write(caller, ino, fh, offset, data, flags),
+            Op::Open { ino, flags } => fs.open(caller, ino, flags),
+            Op::Create {
+                parent,
+                ref name,
+                mode,
+                flags,
+            } => fs.create(caller, parent, OsStr::new(name), mode, flags),
+            Op::Mknod {
+                parent,
+                ref name,
+                mode,
+                rdev,
+            } => fs.mknod(caller, parent, OsStr::new(name), mode, rdev),
+            Op::Unlink { parent, ref name } => fs.unlink(caller, parent, OsStr::new(name)),
+            Op::Setattr { ino, size } => fs.setattr(caller, ino, size),
+        }
+    }
+}
+
+// fuse's FileType, but something serde can write down
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    NamedPipe,
+    CharDevice,
+    BlockDevice,
+    Directory,
+    RegularFile,
+    Symlink,
+    Socket,
+}
+impl From<FileType> for Kind {
+    fn from(kind: FileType) -> Kind {
+        match kind {
+            FileType::NamedPipe => Kind::NamedPipe,
+            FileType::CharDevice => Kind::CharDevice,
+            FileType::BlockDevice => Kind::BlockDevice,
+            FileType::Directory => Kind::Directory,
+            FileType::RegularFile => Kind::RegularFile,
+            FileType::Symlink => Kind::Symlink,
+            FileType::Socket => Kind::Socket,
+        }
+    }
+}
+impl From<Kind> for FileType {
+    fn from(kind: Kind) -> FileType {
+        match kind {
+            Kind::NamedPipe => FileType::NamedPipe,
+            Kind::CharDevice => FileType::CharDevice,
+            Kind::BlockDevice => FileType::BlockDevice,
+            Kind::Directory => FileType::Directory,
+            Kind::RegularFile => FileType::RegularFile,
+            Kind::Symlink => FileType::Symlink,
+            Kind::Socket => FileType::Socket,
+        }
+    }
+}
+
+// fuse's FileAttr, with times as (seconds, nanoseconds)
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Attr {
+    pub ino: u64,
+    pub size: u64,
+    pub blocks: u64,
+    pub atime: (i64, i32),
+    pub mtime: (i64, i32),
+    pub ctime: (i64, i32),
+    pub crtime: (i64, i32),
+    pub kind: Kind,
+    pub perm: u16,
+    pub nlink: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub rdev: u32,
+    pub flags: u32,
+}
+fn from_timespec(t: Timespec) -> (i64, i32) {
+    (t.sec, t.nsec)
+}
+fn to_timespec(t: (i64, i32)) -> Timespec {
+    Timespec::new(t.0, t.1)
+}
+impl From<FileAttr> for Attr {
+    fn from(attr: FileAttr) -> Attr {
+        Attr {
+            ino: attr.ino,
+            size: attr.size,
+            blocks: attr.blocks,
+            atime: from_timespec(attr.atime),
+            mtime: from_timespec(attr.mtime),
+            ctime: from_timespec(attr.ctime),
+            crtime: from_timespec(attr.crtime),
+            kind: attr.kind.into(),
+            perm: attr.perm,
+            nlink: attr.nlink,
+            uid: attr.uid,
+            gid: attr.gid,
+            rdev: attr.rdev,
+            flags: attr.flags,
+        }
+    }
+}
+impl From<Attr> for FileAttr {
+    fn from(attr: Attr) -> FileAttr {
+        FileAttr {
+            ino: attr.ino,
+            size: attr.size,
+            blocks: attr.blocks,
+            atime: to_timespec(attr.atime),
+            mtime: to_timespec(attr.mtime),
+            ctime: to_timespec(attr.ctime),
+            crtime: to_timespec(attr.crtime),
+            kind: attr.kind.into(),
+            perm: attr.perm,
+            nlink: attr.nlink,
+            uid: attr.uid,
+            gid: attr.gid,
+            rdev: attr.rdev,
+            flags: attr.flags,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DirEntry {
+    pub ino: u64,
+    pub offset: i64,
+    pub kind: Kind,
+    pub name: String,
+}
+impl DirEntry {
+    pub fn new(ino: u64, offset: i64, kind: FileType, name: &str) -> DirEntry {
+        DirEntry {
+            ino: ino,
+            offset: offset,
+            kind: kind.into(),
+            name: name.to_string(),
+        }
+    }
+}
+
+// the `entries` of a directory from `offset` on. Each entry's offset is
+// where the next readdir should pick up, so the first one is 1: the kernel
+// starts at 0 and then passes back the offset of the last entry it got.
+pub fn list_from<S: AsRef<str>>(entries: Vec<(u64, FileType, S)>, offset: i64) -> Vec<DirEntry> {
+    entries
+        .into_iter()
+        .enumerate()
+        .skip(offset as usize)
+        .map(|(i, (ino, kind, name))| DirEntry::new(ino, (i + 1) as i64, kind, name.as_ref()))
+        .collect()
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reply {
+    Entry(Attr),
+    Attr(Attr),
+    Data(Vec<u8>),
+    Entries(Vec<DirEntry>),
+    Written(u32),
+    Opened { fh: u64, flags: u32 },
+    Created { attr: Attr, fh: u64, flags: u32 },
+    Empty,
+    Error(i32),
+}
+impl Reply {
+    pub fn entry(attr: FileAttr) -> Reply {
+        Reply::Entry(attr.into())
+    }
+
+    pub fn attr(attr: FileAttr) -> Reply {
+        Reply::Attr(attr.into())
+    }
+
+    pub fn data(data: &[u8]) -> Reply {
+        Reply::Data(data.to_vec())
+    }
+
+    pub fn created(attr: FileAttr, fh: u64, flags: u32) -> Reply {
+        Reply::Created {
+            attr: attr.into(),
+            fh: fh,
+            flags: flags,
+        }
+    }
+
+    // passes the reply on to the kernel. Anything of the wrong kind for
+    // the request is an EIO.
+    pub fn send_entry(self, ttl: &Timespec, reply: ReplyEntry) {
+        match self {
+            Reply::Entry(attr) => reply.entry(ttl, &attr.into(), 0),
+            Reply::Error(errno) => reply.error(errno),
+            _ => reply.error(EIO),
+        }
+    }
+
+    pub fn send_attr(self, ttl: &Timespec, reply: ReplyAttr) {
+        match self {
+            Reply::Attr(attr) => reply.attr(ttl, &attr.into()),
+            Reply::Error(errno) => reply.error(errno),
+            _ => reply.error(EIO),
+        }
+    }
+
+    pub fn send_data(self, reply: ReplyData) {
+        match self {
+            Reply::Data(data) => reply.data(&data),
+            Reply::Error(errno) => reply.error(errno),
+            _ => reply.error(EIO),
+        }
+    }
+
+    pub fn send_entries(self, mut reply: ReplyDirectory) {
+        match self {
+            Reply::Entries(entries) => {
+                // `add` says when the kernel's buffer is full
+                for entry in entries {
+                    if reply.add(entry.ino, entry.offset, entry.kind.into(), entry.name) {
+                        break;
+                    }
+                }
+                reply.ok();
+            }
+            Reply::Error(errno) => reply.error(errno),
+            _ => reply.error(EIO),
+        }
+    }
+
+    pub fn send_written(self, reply: ReplyWrite) {
+        match self {
+            Reply::Written(size) => reply.written(size),
+            Reply::Error(errno) => reply.error(errno),
+            _ => reply.error(EIO),
+        }
+    }
+
+    pub fn send_opened(self, reply: ReplyOpen) {
+        match self {
+            Reply::Opened { fh, flags } => reply.opened(fh, flags),
+            Reply::Error(errno) => reply.error(errno),
+            _ => reply.error(EIO),
+        }
+    }
+
+    pub fn send_created(self, ttl: &Timespec, reply: ReplyCreate) {
+        match self {
+            Reply::Created { attr, fh, flags } => reply.created(ttl, &attr.into(), 0, fh, flags),
+            Reply::Error(errno) => reply.error(errno),
+            _ => reply.error(EIO),
+        }
+    }
+
+    pub fn send_empty(self, reply: ReplyEmpty) {
+        match self {
+            Reply::Empty => reply.ok(),
+            Reply::Error(errno) => reply.error(errno),
+            _ => reply.error(EIO),
+        }
+    }
+}
+
+// the operations that get recorded, answered without a kernel in the way.
+// The ones with a default answer the way fuse does for a file-system that
+// doesn't implement them.
+pub trait Ops {
+    fn lookup(&mut self, caller: &Caller, parent: u64, name: &OsStr) -> Reply;
+    fn getattr(&mut self, caller: &Caller, ino: u64) -> Reply;
+    fn read(&mut self, caller: &Caller, ino: u64, fh: u64, offset: i64, size: u32) -> Reply;
+    fn readdir(&mut self, caller: &Caller, ino: u64, fh: u64, offset: i64) -> Reply;
+    fn write(
+        &mut self,
+        caller: &Caller,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        data: &[u8],
+        flags: u32,
+    ) -> Reply;
+
+    fn open(&mut self, _caller: &Caller, _ino: u64, _flags: u32) -> Reply {
+        Reply::Opened { fh: 0, flags: 0 }
+    }
+
+    fn create(
+        &mut self,
+        _caller: &Caller,
+        _parent: u64,
+        _name: &OsStr,
+        _mode: u32,
+        _flags: u32,
+    ) -> Reply {
+        Reply::Error(ENOSYS)
+    }
+
+    fn mknod(
+        &mut self,
+        _caller: &Caller,
+        _parent: u64,
+        _name: &OsStr,
+        _mode: u32,
+        _rdev: u32,
+    ) -> Reply {
+        Reply::Error(ENOSYS)
+    }
+
+    fn unlink(&mut self, _caller: &Caller, _parent: u64, _name: &OsStr) -> Reply {
+        Reply::Error(ENOSYS)
+    }
+
+    fn setattr(&mut self, _caller: &Caller, _ino: u64, _size: Option<u64>) -> Reply {
+        Reply::Error(ENOSYS)
+    }
+
+    // where to write down requests, if anywhere
+    fn recorder(&mut self) -> Option<&mut Recorder>;
+}
+
+// the first line of a trace
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Header {
+    // milliseconds since the epoch
+    pub started_at: i64,
+    // for whichever random number generators the file-system has
+    pub seeds: Vec<u64>,
+}
+impl Header {
+    // a header for a game starting now, with `seeds` seeds
+    pub fn now(seeds: usize) -> Header {
+        let started_at = speedrun::now_ms();
+        Header {
+            started_at: started_at,
+            seeds: (0..seeds as u64).map(|i| started_at as u64 + i).collect(),
+        }
+    }
+
+    // reads the header of the trace at `path`
+    pub fn read(path: &Path) -> Result<Header, String> {
+        let file = File::open(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        let line = match BufReader::new(file).lines().next() {
+            Some(line) => line.map_err(|e| format!("can't read {}: {}", path.display(), e))?,
+            None => return Err(format!("{}: empty trace", path.display())),
+        };
+        serde_json::from_str(&line).map_err(|e| format!("{}:1: bad header: {}", path.display(), e))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Record {
+    // milliseconds since the epoch
+    at: i64,
+    caller: Caller,
+    op: Op,
+    reply: Reply,
+}
+
+pub struct Recorder {
+    path: PathBuf,
+    file: File,
+}
+impl Recorder {
+    // starts a new trace at `path`, beginning with `header`
+    pub fn create(path: &Path, header: &Header) -> Result<Recorder, String> {
+        let error = |e| format!("can't record to {}: {}", path.display(), e);
+        let mut file = File::create(path).map_err(&error)?;
+        // a Header is always valid JSON
+        writeln!(file, "{}", serde_json::to_string(header).unwrap()).map_err(&error)?;
+        Ok(Recorder {
+            path: path.to_path_buf(),
+            file: file,
+        })
+    }
+
+    fn record(&mut self, at: i64, caller: &Caller, op: &Op, reply: &Reply) {
+        let record = Record {
+            at: at,
+            caller: *caller,
+            op: op.clone(),
+            reply: reply.clone(),
+        };
+        // a Record is always valid JSON
+        let line = serde_json::to_string(&record).unwrap();
+        if let Err(e) = writeln!(self.file, "{}", line) {
+            eprintln!("can't record to {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+// answers `op` from `req`, writing it down if `fs` is recording
+pub fn run<F: Ops>(fs: &mut F, req: &Request, op: Op) -> Reply {
+    let caller = Caller::from(req);
+    let at = speedrun::now_ms();
+    let reply = speedrun::stopped_at(at, || op.apply(fs, &caller));
+    if let Some(recorder) = fs.recorder() {
+        recorder.record(at, &caller, &op, &reply);
+    }
+    reply
+}
+
+pub struct Mismatch {
+    // which line of the trace
+    pub line: usize,
+    pub op: Op,
+    pub expected: Reply,
+    pub got: Reply,
+}
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // ops and replies are always valid JSON
+        write!(
+            f,
+            "line {}: {}\n  recorded: {}\n  replayed: {}",
+            self.line,
+            serde_json::to_string(&self.op).unwrap(),
+            serde_json::to_string(&self.expected).unwrap(),
+            serde_json::to_string(&self.got).unwrap()
+        )
+    }
+}
+
+// sends every request in the trace at `path` to `fs` at the time it was
+// recorded, returning how many there were and the replies that came out
+// different. `fs` should be built from the trace's `Header`.
+pub fn replay<F: Ops>(fs: &mut F, path: &Path) -> Result<(usize, Vec<Mismatch>), String> {
+    let file = File::open(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+    let mut count = 0;
+    let mut mismatches = Vec::new();
+    // the header's on the first line
+    for (i, line) in BufReader::new(file).lines().enumerate().skip(1) {
+        let line = line.map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record = serde_json::from_str(&line)
+            .map_err(|e| format!("{}:{}: bad record: {}", path.display(), i + 1, e))?;
+        let got = speedrun::stopped_at(record.at, || record.op.apply(fs, &record.caller));
+        count += 1;
+        if got != record.reply {
+            mismatches.push(Mismatch {
+                line: i + 1,
+                op: record.op,
+                expected: record.reply,
+                got: got,
+            });
+        }
+    }
+    Ok((count, mismatches))
+}
+
+// replays the trace at `path` against `fs`, says how it went and exits: 0
+// if every reply matched, 1 if any didn't and 2 if the trace was unusable
+pub fn replay_and_exit<F: Ops>(fs: &mut F, path: &Path) -> ! {
+    match replay(fs, path) {
+        Ok((count, mismatches)) => {
+            for mismatch in mismatches.iter() {
+                println!("{}", mismatch);
+            }
+            println!(
+                "replayed {} requests, {} replies didn't match",
+                count,
+                mismatches.len()
+            );
+            process::exit(if mismatches.is_empty() { 0 } else { 1 });
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    }
+}
+
+// what `--record <trace>` and `--replay <trace>` asked for
+#[derive(Debug, Default)]
+pub struct TraceArgs {
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+}
+impl TraceArgs {
+    // takes `--record` and `--replay` out of `args`, returning the rest
+    pub fn parse<I: Iterator<Item = OsString>>(
+        mut args: I,
+    ) -> Result<(TraceArgs, Vec<OsString>), String> {
+        let mut trace = TraceArgs::default();
+        let mut rest = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("--record") => {
+                    trace.record = Some(args.next().ok_or("--record needs a file")?.into())
+                }
+                Some("--replay") => {
+                    trace.replay = Some(args.next().ok_or("--replay needs a file")?.into())
+                }
+                _ => rest.push(arg),
+            }
+        }
+        if trace.record.is_some() && trace.replay.is_some() {
+            return Err("use either --record or --replay, not both".to_string());
+        }
+        Ok((trace, rest))
+    }
+
+    // the header to build the file-system from: the one `--replay` is
+    // replaying, or a new one. Either way it has at least `seeds` seeds.
+    pub fn header(&self, seeds: usize) -> Result<Header, String> {
+        let path = match self.replay {
+            Some(ref path) => path,
+            None => return Ok(Header::now(seeds)),
+        };
+        let header = Header::read(path)?;
+        if header.seeds.len() < seeds {
+            return Err(format!(
+                "{}: wanted {} seeds, the trace has {}",
+                path.display(),
+                seeds,
+                header.seeds.len()
+            ));
+        }
+        Ok(header)
+    }
+
+    // the recorder `--record` asked for, starting with `header`
+    pub fn recorder(&self, header: &Header) -> Result<Option<Recorder>, String> {
+        match self.record {
+            Some(ref path) => Recorder::create(path, header).map(Some),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+// Kittens that wander off.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use control::is_control_dir;
+use rng::Rng;
+use timer::{Every, Timer};
+use {update_parent_map, Either, Game, GameDir, GameFile};
+
+pub type Wandering = Every;
+
+// where a kitten went, as far as the journal lets on
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Hint {
+    NextDoor,
+    Deeper,
+}
+impl Hint {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Hint::NextDoor => "padded off next door",
+            Hint::Deeper => "crept somewhere deeper",
+        }
+    }
+}
+
+pub struct Wander {
+    pub timer: Timer,
+    rng: Rng,
+    // the last place each kitten wandered to, by inode
+    hints: BTreeMap<u64, Hint>,
+}
+impl Wander {
+    pub fn new(settings: Wandering, seed: u64) -> Wander {
+        Wander {
+            timer: Timer::new(settings),
+            rng: Rng::new(seed),
+            hints: BTreeMap::new(),
+        }
+    }
+
+    pub fn forget(&mut self) {
+        self.hints.clear();
+    }
+}
+
+// kittens stay out of the control dir and locked rooms
+fn can_wander_into(dir: &GameDir) -> bool {
+    !is_control_dir(dir) && !dir.is_locked()
+}
+
+type Move = (
+    Rc<RefCell<GameFile>>,
+    Rc<RefCell<GameDir>>,
+    Vec<(Rc<RefCell<GameDir>>, Hint)>,
+);
+
+// every needy kitten in (or under) `dir`, where it is and where it could go
+fn moves(dir: &Rc<RefCell<GameDir>>, parent: Option<&Rc<RefCell<GameDir>>>, moves: &mut Vec<Move>) {
+    let borrowed = dir.borrow();
+    let mut candidates = Vec::new();
+    if let Some(parent) = parent {
+        for sibling in parent.borrow().sub_dirs.iter() {
+            if !Rc::ptr_eq(sibling, dir) && can_wander_into(&sibling.borrow()) {
+                candidates.push((Rc::clone(sibling), Hint::NextDoor));
+            }
+        }
+    }
+    for child in borrowed
+        .sub_dirs
+        .iter()
+        .filter(|d| can_wander_into(&d.borrow()))
+    {
+        candidates.push((Rc::clone(child), Hint::Deeper));
+    }
+    if !candidates.is_empty() {
+        for f in borrowed.files.iter() {
+            let needy = {
+                let kitten = f.borrow();
+                kitten.is_kitten() && kitten.life > 0
+            };
+            if needy {
+                moves.push((Rc::clone(f), Rc::clone(dir), candidates.clone()));
+            }
+        }
+    }
+    for sub_dir in borrowed.sub_dirs.iter() {
+        self::moves(sub_dir, Some(dir), moves);
+    }
+}
+
+type Stray = (Rc<RefCell<GameFile>>, Rc<RefCell<GameDir>>);
+
+// every kitten in (or under) `dir` that's away from home, and where it is.
+// Ones that found their own way back are home again.
+fn strays_in(dir: &Rc<RefCell<GameDir>>, strays: &mut Vec<Stray>) {
+    let borrowed = dir.borrow();
+    for f in borrowed.files.iter() {
+        let mut kitten = f.borrow_mut();
+        if kitten.home == Some(borrowed.inode) {
+            kitten.home = None;
+        } else if kitten.home.is_some() {
+            strays.push((Rc::clone(f), Rc::clone(dir)));
+        }
+    }
+    for sub_dir in borrowed.sub_dirs.iter() {
+        strays_in(sub_dir, strays);
+    }
+}
+
+fn name_taken(dir: &GameDir, name: &str) -> bool {
+    dir.files.iter().any(|f| f.borrow().name == name)
+        || dir.sub_dirs.iter().any(|d| d.borrow().name == name)
+}
+
+impl Game {
+    // called on every operation that looks around or pets something. If
+    // that moved a kitten, changed one's life or let a dog in, the game's
+    // saved straight away rather than on the next write.
+    pub fn tick(&mut self) {
+        let now = self.now();
+        let mut changed = false;
+        if self.paused_at.is_none() && self.wander.timer.due(now) {
+            self.wander();
+            changed = true;
+        }
+        if self.paused_at.is_none() {
+            changed |= self.tick_scripts(now);
+        }
+        changed |= self.tick_dogs(now);
+        if changed {
+            self.save();
+        }
+    }
+
+    // moves every needy kitten to a random neighbouring directory (unless
+    // there's already something with its name there).
+    pub fn wander(&mut self) {
+        let mut planned = Vec::new();
+        moves(&self.gamedir, None, &mut planned);
+        for (kitten, from, candidates) in planned {
+            let (ref to, hint) = *self.wander.rng.pick(&candidates);
+            let (inode, name) = {
+                let borrowed = kitten.borrow();
+                (borrowed.inode, borrowed.name.clone())
+            };
+            if name_taken(&to.borrow(), &name) {
+                continue;
+            }
+            kitten.borrow_mut().home.get_or_insert(from.borrow().inode);
+            from.borrow_mut().files.retain(|f| !Rc::ptr_eq(f, &kitten));
+            to.borrow_mut().files.push(kitten);
+            self.wander.hints.insert(inode, hint);
+        }
+        self.parent_table.clear();
+        update_parent_map(&self.gamedir, &mut self.parent_table);
+    }
+
+    // sends every kitten that wandered back to where it started, for a
+    // reset. One that finds something else with its name back home stays
+    // where it is (and tries again next time).
+    pub fn send_wanderers_home(&mut self) {
+        let mut strays = Vec::new();
+        strays_in(&self.gamedir, &mut strays);
+        for (kitten, from) in strays.iter() {
+            from.borrow_mut().files.retain(|f| !Rc::ptr_eq(f, kitten));
+        }
+        for (kitten, from) in strays {
+            let home = match kitten.borrow().home.and_then(|h| self.inode_table.get(&h)) {
+                Some(Either::Directory { dir }) => Some(Rc::clone(dir)),
+                _ => None,
+            };
+            let home = home.filter(|h| !name_taken(&h.borrow(), &kitten.borrow().name));
+            match home {
+                Some(home) => {
+                    kitten.borrow_mut().home = None;
+                    home.borrow_mut().files.push(kitten);
+                }
+                None => from.borrow_mut().files.push(kitten),
+            }
+        }
+        self.reindex();
+    }
+
+    // hints for the needy kittens that have wandered. Hidden kittens keep
+    // their secrets.
+    pub fn wanderings(&self) -> Vec<(String, Hint)> {
+        self.wander
+            .hints
+            .iter()
+            .filter_map(|(inode, hint)| {
+                let kitten = self.inode_table.get(inode).and_then(Either::kitten)?;
+                let kitten = kitten.borrow();
+                if kitten.life > 0 && !kitten.is_hidden() {
+                    Some((kitten.name.clone(), *hint))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn hints_content(wanderings: &[(String, Hint)]) -> String {
+    if wanderings.is_empty() {
+        return String::new();
+    }
+    let mut content = "\nWhere did they go?\n\n".to_string();
+    for (name, hint) in wanderings.iter() {
+        content.push_str(&format!("* {} {}\n", name, hint.describe()));
+    }
+    content
+}
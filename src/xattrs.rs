@@ -0,0 +1,48 @@
+// Extended attributes, for scripts that would rather not parse ASCII art.
+
+use libc::{c_int, EACCES, ENOENT, ENOTSUP};
+use std::ffi::OsStr;
+use time::{self, Timespec};
+
+use {Game, GameFile, Mood};
+
+pub const PREFIX: &str = "user.fuschia.";
+
+impl Game {
+    // the attributes on `ino`, in the order `listxattr` gives them. Only
+    // kittens have any.
+    pub fn xattrs(&self, ino: u64) -> Result<Vec<(String, String)>, c_int> {
+        let either = self.inode_table.get(&ino).ok_or(ENOENT)?;
+        self.catch_up(either);
+        match either.kitten() {
+            Some(f) => Ok(self.kitten_xattrs(&f.borrow())),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn kitten_xattrs(&self, kitten: &GameFile) -> Vec<(String, String)> {
+        let pets = self.pets.iter().filter(|p| p.inode == kitten.inode);
+        let pets_received = pets.clone().count();
+        let last_petted = match pets.map(|p| p.at).max() {
+            Some(at) => format!("{}", time::at_utc(Timespec::new(at, 0)).rfc3339()),
+            None => "never".to_string(),
+        };
+        vec![
+            ("life", kitten.life.to_string()),
+            ("state", Mood::of(kitten.life).name().to_string()),
+            ("pets_received", pets_received.to_string()),
+            ("last_petted", last_petted),
+        ]
+        .into_iter()
+        .map(|(name, value)| (format!("{}{}", PREFIX, name), value))
+        .collect()
+    }
+}
+
+// what `setxattr` and `removexattr` say about `name`
+pub fn read_only(name: &OsStr) -> c_int {
+    match name.to_str() {
+        Some(name) if name.starts_with(PREFIX) => EACCES,
+        _ => ENOTSUP,
+    }
+}